@@ -0,0 +1,210 @@
+use crate::*;
+use std::cmp::Ordering;
+
+/// This type is a drift-free clock, for simulations advancing time by many small steps.
+///
+/// The generic parameter `T` should typically implement [`num_traits::float::Float`] and can usually be inferred.
+///
+/// The current time is kept as a double-word $t_h+t_l$, and each step is added with [`two_sum`],
+/// so that after $n$ steps of $\delta t$ the clock reads $n\,\delta t$ (rounded once)
+/// instead of drifting away from it like `t += dt` does.
+///
+/// The clock can be compared exactly with event times, with the [`PartialEq`] and [`PartialOrd`] operators
+/// or [`CompensatedClock::cmp_time()`]: the comparison takes the low-order part into account,
+/// hence it is consistent with the exact sum of the steps even when [`CompensatedClock::now()`] rounds to the event time.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::CompensatedClock;
+/// let mut clock = CompensatedClock::new();
+/// let mut t = 0.0;
+/// for _ in 0..1_000_000 {
+///     clock += 0.1;
+///     t += 0.1;
+/// }
+/// assert_eq!(clock.now(), 100_000.0);
+/// assert_ne!(t, 100_000.0);
+/// ```
+///
+/// Since `0.1` is slightly larger than $1/10$, ten steps of `0.1` are past `1.0`, although `now()` rounds to it.
+///
+/// ```
+/// # use compensated_summation::CompensatedClock;
+/// let mut clock = CompensatedClock::new();
+/// for _ in 0..10 {
+///     clock.advance(0.1);
+/// }
+/// assert_eq!(clock.now(), 1.0);
+/// assert!(clock > 1.0);
+/// assert!(clock != 1.0);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompensatedClock<T> {
+    /// Current time, as a double-word.
+    time: (T, T),
+}
+
+impl<T: Float> CompensatedClock<T> {
+    /// Create a new clock at time zero.
+    pub fn new() -> Self {
+        Self::starting_at(T::zero())
+    }
+
+    /// Create a new clock at the given time.
+    pub fn starting_at(t: T) -> Self {
+        Self {
+            time: (t, T::zero()),
+        }
+    }
+
+    /// Advance the clock by `dt`.
+    pub fn advance(&mut self, dt: T) {
+        self.time = add_dw(self.time, dt);
+    }
+
+    /// Get the current time, rounded to nearest.
+    pub fn now(&self) -> T {
+        self.time.0 + self.time.1
+    }
+
+    /// Get the current time as a double-word $(t_h,t_l)$, where $t_h$ is the current time rounded to nearest.
+    pub fn now_dw(&self) -> (T, T) {
+        self.time
+    }
+
+    /// Compare the exact current time with the time `t`.
+    ///
+    /// This is `None` if `t` or the current time is NaN.
+    pub fn cmp_time(&self, t: T) -> Option<Ordering> {
+        let (h, l) = self.time;
+        if !h.is_finite() || !t.is_finite() {
+            return h.partial_cmp(&t);
+        }
+        // The exact difference `h + l - t` as a nonoverlapping expansion, whose sign is the one of its largest component.
+        let (d, δd) = two_sum(h, -t);
+        let (q, e0) = two_sum(l, δd);
+        let (q, e1) = two_sum(q, d);
+        [q, e1, e0]
+            .into_iter()
+            .find(|&x| x != T::zero())
+            .unwrap_or(T::zero())
+            .partial_cmp(&T::zero())
+    }
+
+    /// Move the origin of time to `epoch`, so that the clock reads the time elapsed since `epoch`.
+    ///
+    /// The difference with the high-order part is computed exactly with [`two_sum`] and the low-order part is kept,
+    /// so that no accumulated error is lost; the only rounding is in renormalizing the resulting double-word.
+    pub fn reset_epoch(&mut self, epoch: T) {
+        let (h, l) = self.time;
+        let (d, δd) = two_sum(h, -epoch);
+        self.time = add_dw((d, l), δd);
+    }
+}
+
+impl<T: Float> Default for CompensatedClock<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> AddAssign<T> for CompensatedClock<T> {
+    fn add_assign(&mut self, rhs: T) {
+        self.advance(rhs);
+    }
+}
+
+impl<T: Float> AddAssign<&T> for CompensatedClock<T> {
+    fn add_assign(&mut self, rhs: &T) {
+        self.advance(*rhs);
+    }
+}
+
+impl<T: Float, V> Add<V> for CompensatedClock<T>
+where
+    Self: AddAssign<V>,
+{
+    type Output = Self;
+    fn add(mut self, rhs: V) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl<T: Float> PartialEq<T> for CompensatedClock<T> {
+    fn eq(&self, other: &T) -> bool {
+        self.cmp_time(*other) == Some(Ordering::Equal)
+    }
+}
+
+impl<T: Float> PartialOrd<T> for CompensatedClock<T> {
+    fn partial_cmp(&self, other: &T) -> Option<Ordering> {
+        self.cmp_time(*other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+    use std::cmp::Ordering;
+
+    #[test]
+    fn clock_no_drift() {
+        for dt in [0.1f32, 1.0 / 3.0, 1e-3, 0.7] {
+            let mut clock = CompensatedClock::new();
+            let mut naive = 0f32;
+            let n = 1_000_000;
+            for _ in 0..n {
+                clock += dt;
+                naive += dt;
+            }
+            let exact = (n as f64 * dt as f64) as f32;
+            assert_eq!(clock.now(), exact);
+            assert!((naive - exact).abs() > 100.0 * f32::EPSILON * exact);
+        }
+    }
+
+    #[test]
+    fn clock_events() {
+        use rand::prelude::*;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        // With `f32` steps on a grid of `2^-30`, the exact time is known in `f64`
+        // and the double-word of the clock is exact too.
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+        let mut clock = CompensatedClock::starting_at(1.0f32);
+        let mut exact = 1f64;
+        for _ in 0..10_000 {
+            let dt = rng.gen_range(0..1 << 20) as f32 * 2f32.powi(-30);
+            clock += dt;
+            exact += dt as f64;
+            let (h, l) = clock.now_dw();
+            assert_eq!(h as f64 + l as f64, exact);
+            for t in [exact as f32, h, h + l, f32::from_bits(h.to_bits() + 1)] {
+                assert_eq!(clock.cmp_time(t), exact.partial_cmp(&(t as f64)));
+            }
+        }
+        assert!(clock.cmp_time(f32::NAN).is_none());
+        assert!(clock < f32::INFINITY);
+    }
+
+    #[test]
+    fn clock_reset_epoch() {
+        let mut clock = CompensatedClock::new();
+        for _ in 0..10 {
+            clock += 0.1;
+        }
+        assert_eq!(clock.cmp_time(1.0), Some(Ordering::Greater));
+        clock.reset_epoch(1.0);
+        // Ten times `0.1` is exactly `1 + 2^-54`.
+        assert_eq!(clock.now(), 2f64.powi(-54));
+        assert!(clock > 0.0);
+        for _ in 0..10 {
+            clock += 0.1;
+        }
+        assert_eq!(clock.now(), 1.0);
+        assert!(clock > 1.0);
+        assert_eq!(CompensatedClock::<f64>::default(), 0.0);
+    }
+}
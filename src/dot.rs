@@ -0,0 +1,396 @@
+use crate::*;
+
+/// `Dot2` algorithm, see [Ogita, Rump, Oishi (2005)](https://doi.org/10.1137/030601818).
+///
+/// **Input:** two slices $x$ and $y$ of floating-point numbers of the same length.
+///
+/// **Output:** the dot product $\sum_i x_i y_i$, computed as if in twice the working precision and then rounded back to working precision.
+///
+/// Each product is split with [`two_prod`] and each addition with [`two_sum`];
+/// all the errors are accumulated in a separate compensation term, which is added back at the end.
+///
+/// # Panics
+///
+/// Panics if `x` and `y` have different lengths.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::dot2;
+/// let x = [1e16, 1.0, -1e16];
+/// let y = [1.0, 1.0, 1.0];
+/// assert_eq!(dot2(&x, &y), 1.0);
+/// ```
+pub fn dot2<T: Float>(x: &[T], y: &[T]) -> T {
+    assert_eq!(x.len(), y.len(), "slices must have the same length");
+    x.iter().zip(y).sum::<DotAccumulator<T>>().total()
+}
+
+/// `DotK` algorithm, see [Ogita, Rump, Oishi (2005)](https://doi.org/10.1137/030601818).
+///
+/// **Input:** two slices $x$ and $y$ of floating-point numbers of the same length, and the number $K\geq1$ of folds.
+///
+/// **Output:** the dot product $\sum_i x_i y_i$, computed as if in $K$-fold working precision and then rounded back to working precision.
+///
+/// All the products are transformed with [`two_prod`] and summed with [`two_sum`], collecting
+/// the $2n$ errors in a vector; this vector is then summed with $K-2$ error-free vector
+/// transformations followed by a recursive summation.
+/// For $K=1$ this is the ordinary dot product and for $K=2$ it is equivalent to [`dot2`].
+///
+/// Contrary to [`dot2`], this function allocates a temporary vector of length $2n$.
+///
+/// # Panics
+///
+/// Panics if `x` and `y` have different lengths or if `k` is zero.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::dot_k;
+/// let x = [1e100, 1.0, -1e100, 1e-100];
+/// let y = [1e100, 1.0, 1e100, 1e100];
+/// assert_eq!(dot_k(&x, &y, 3), 2.0);
+/// ```
+pub fn dot_k<T: Float>(x: &[T], y: &[T], k: usize) -> T {
+    assert_eq!(x.len(), y.len(), "slices must have the same length");
+    assert!(k >= 1, "the number of folds must be positive");
+    if k == 1 || x.is_empty() {
+        return x.iter().zip(y).fold(T::zero(), |s, (&a, &b)| s + a * b);
+    }
+    let n = x.len();
+    let mut r = Vec::with_capacity(2 * n);
+    let (mut p, e) = two_prod(x[0], y[0]);
+    r.push(e);
+    for i in 1..n {
+        let (h, e) = two_prod(x[i], y[i]);
+        let (s, q) = two_sum(p, h);
+        p = s;
+        r.push(e);
+        r.push(q);
+    }
+    r.push(p);
+    for _ in 2..k {
+        vec_sum(&mut r);
+    }
+    let (&last, rest) = r.split_last().unwrap();
+    rest.iter().fold(T::zero(), |s, &x| s + x) + last
+}
+
+/// Error-free vector transformation `VecSum` (in place): the last element becomes the
+/// floating-point sum and the others hold the errors, the exact sum being unchanged.
+fn vec_sum<T: Float>(p: &mut [T]) {
+    for i in 1..p.len() {
+        let (s, e) = two_sum(p[i], p[i - 1]);
+        p[i] = s;
+        p[i - 1] = e;
+    }
+}
+
+/// Exact dot product, correctly rounded.
+///
+/// **Input:** two slices $x$ and $y$ of floating-point numbers of the same length.
+///
+/// **Output:** the dot product $\sum_i x_i y_i$ rounded to nearest.
+///
+/// Each product is split exactly with [`two_prod`] and both parts are added to an [`ExactSum`].
+/// The result is correctly rounded provided that no product overflows or underflows.
+///
+/// # Panics
+///
+/// Panics if `x` and `y` have different lengths.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::dot_exact;
+/// let e = f64::EPSILON;
+/// let x = [1.0 + e, -1.0, 1e-300];
+/// let y = [1.0 - e, 1.0, 1e-10];
+/// assert_eq!(dot_exact(&x, &y), -e * e);
+/// ```
+pub fn dot_exact<T: Float>(x: &[T], y: &[T]) -> T {
+    assert_eq!(x.len(), y.len(), "slices must have the same length");
+    let mut sum = ExactSum::new();
+    for (&a, &b) in x.iter().zip(y) {
+        let (p, e) = two_prod(a, b);
+        sum += p;
+        sum += e;
+    }
+    sum.total()
+}
+
+/// This type is an accumulator for computing a dot product with the [`dot2`] algorithm.
+///
+/// The generic parameter `T` should typically implement [`num_traits::float::Float`] and can usually be inferred.
+///
+/// # Examples
+///
+/// You can create a new empty accumulator with [`DotAccumulator::new()`];
+/// then you can add (and subtract) products given as pairs of floating-point numbers;
+/// when you are done, you can retrieve the total with the [`DotAccumulator::total()`] method.
+///
+/// ```
+/// # use compensated_summation::DotAccumulator;
+/// let mut dot = DotAccumulator::new();
+/// dot += (1e16, 1.0);
+/// dot += (1.0, 1.0);
+/// dot -= (1e16, 1.0);
+/// assert_eq!(dot.total(), 1.0);
+/// ```
+///
+/// In addition, [`DotAccumulator`] implements the [`std::iter::Sum`](#impl-Sum<V>-for-DotAccumulator<T>) trait,
+/// which means that an iterator of pairs (such as two zipped slices) can be summed
+///
+/// ```
+/// # use compensated_summation::DotAccumulator;
+/// let x = [1e16, 1.0, -1e16];
+/// let y = [1.0, 1.0, 1.0];
+/// assert_eq!(x.iter().zip(&y).sum::<DotAccumulator<_>>().total(), 1.0);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct DotAccumulator<T> {
+    /// Accumulated sum of the products.
+    pub sum: T,
+    /// Compensation of the error.
+    pub comp: T,
+}
+
+impl<T: Float> DotAccumulator<T> {
+    /// Create a new empty accumulator.
+    pub fn new() -> Self {
+        Self {
+            sum: T::zero(),
+            comp: T::zero(),
+        }
+    }
+
+    /// Get the estimated dot product.
+    pub fn total(&self) -> T {
+        self.sum + self.comp
+    }
+
+    /// Merge another accumulator into this one, as if all its pairs had been added to this one.
+    pub fn merge(&mut self, other: &Self) {
+        let (s, c) = two_sum(self.sum, other.sum);
+        self.sum = s;
+        self.comp = self.comp + (other.comp + c);
+    }
+}
+
+impl<T: Float> Default for DotAccumulator<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> Accumulator for DotAccumulator<T> {
+    type Output = T;
+    fn total(&self) -> T {
+        self.total()
+    }
+    fn merge(&mut self, other: &Self) {
+        self.merge(other)
+    }
+}
+
+impl<T: Float> AddAssign<(T, T)> for DotAccumulator<T> {
+    fn add_assign(&mut self, (x, y): (T, T)) {
+        let (h, r) = two_prod(x, y);
+        let (s, q) = two_sum(self.sum, h);
+        self.sum = s;
+        self.comp = self.comp + (q + r);
+    }
+}
+
+impl<T: Float> SubAssign<(T, T)> for DotAccumulator<T> {
+    fn sub_assign(&mut self, (x, y): (T, T)) {
+        *self += (-x, y);
+    }
+}
+
+impl<T: Float> AddAssign<(&T, &T)> for DotAccumulator<T> {
+    fn add_assign(&mut self, (x, y): (&T, &T)) {
+        *self += (*x, *y);
+    }
+}
+
+impl<T: Float> SubAssign<(&T, &T)> for DotAccumulator<T> {
+    fn sub_assign(&mut self, (x, y): (&T, &T)) {
+        *self -= (*x, *y);
+    }
+}
+
+impl<T: Float, V> Add<V> for DotAccumulator<T>
+where
+    Self: AddAssign<V>,
+{
+    type Output = Self;
+    fn add(mut self, rhs: V) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl<T: Float, V> Sub<V> for DotAccumulator<T>
+where
+    Self: SubAssign<V>,
+{
+    type Output = Self;
+    fn sub(mut self, rhs: V) -> Self::Output {
+        self -= rhs;
+        self
+    }
+}
+
+impl<T: Float, V> Sum<V> for DotAccumulator<T>
+where
+    Self: AddAssign<V>,
+{
+    fn sum<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = V>,
+    {
+        let mut dot = DotAccumulator::new();
+        for p in iter {
+            dot += p;
+        }
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn test_two_prod() {
+        use rand::prelude::*;
+        use rand_distr::LogNormal;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+        let dist = LogNormal::new(0.0, 10.0).unwrap();
+
+        for _ in 0..1000 {
+            let a: f64 = rng.sample(dist);
+            let b: f64 = rng.sample(dist);
+            let (p, e) = two_prod(a, b);
+            assert_eq!(p, a * b);
+            // The error is exactly representable and smaller than half an ulp of the product.
+            assert!(e.abs() <= p.abs() * f64::EPSILON / 2.0);
+        }
+    }
+
+    #[test]
+    fn dot2_cancellation() {
+        let x = [1e100, 1.0, -1e100, 1.0];
+        let y = [1.0, 1.0, 1.0, 1.0];
+        assert_eq!(dot2(&x, &y), 2.0);
+        assert_eq!(x.iter().zip(&y).map(|(a, b)| a * b).sum::<f64>(), 1.0);
+    }
+
+    #[test]
+    fn dot2_product_error() {
+        // (1 + ε)(1 - ε) - 1 = -ε², which is lost by a naive dot product.
+        let e = f64::EPSILON;
+        let x = [1.0 + e, -1.0];
+        let y = [1.0 - e, 1.0];
+        assert_eq!(dot2(&x, &y), -e * e);
+        assert_eq!(x[0] * y[0] + x[1] * y[1], 0.0);
+    }
+
+    #[test]
+    fn dot2_empty() {
+        assert_eq!(dot2::<f64>(&[], &[]), 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn dot2_length_mismatch() {
+        dot2(&[1.0, 2.0], &[1.0]);
+    }
+
+    /// Generate an ill-conditioned dot product as in Algorithm 6.1 of Ogita, Rump, Oishi (2005):
+    /// the exact dot product is of order 1 while the terms are of order `2^b`.
+    fn ill_conditioned(rng: &mut impl rand::Rng, n: usize, b: i32) -> (Vec<f64>, Vec<f64>) {
+        let mut x = Vec::with_capacity(n);
+        let mut y = Vec::with_capacity(n);
+        let half = n / 2;
+        for i in 0..half {
+            let e = (i as i32 * b) / half as i32;
+            x.push((2.0 * rng.gen::<f64>() - 1.0) * 2f64.powi(e));
+            y.push((2.0 * rng.gen::<f64>() - 1.0) * 2f64.powi(e));
+        }
+        for i in half..n {
+            let e = ((n - 1 - i) as i32 * b) / (n - half) as i32;
+            let a = (2.0 * rng.gen::<f64>() - 1.0) * 2f64.powi(e);
+            x.push(a);
+            let d = dot_exact(&x[..i], &y);
+            y.push(((2.0 * rng.gen::<f64>() - 1.0) * 2f64.powi(e) - d) / a);
+        }
+        (x, y)
+    }
+
+    #[test]
+    fn dot_k_ill_conditioned() {
+        use rand::prelude::*;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+        for _ in 0..20 {
+            let (x, y) = ill_conditioned(&mut rng, 100, 100);
+            let exact = dot_exact(&x, &y);
+            // The condition number is about 2^200, so two folds are not enough, but five are.
+            assert!((dot2(&x, &y) - exact).abs() >= exact.abs() / 2.0);
+            let approx = dot_k(&x, &y, 5);
+            assert!((approx - exact).abs() <= exact.abs() * f64::EPSILON);
+        }
+    }
+
+    #[test]
+    fn dot_k_small_k() {
+        let x = [1e100, 1.0, -1e100, 1.0];
+        let y = [1.0, 1.0, 1.0, 1.0];
+        assert_eq!(dot_k(&x, &y, 1), 1.0);
+        assert_eq!(dot_k(&x, &y, 2), dot2(&x, &y));
+        assert_eq!(dot_k::<f64>(&[], &[], 3), 0.0);
+        assert_eq!(dot_k(&[3.0], &[0.1], 3), 3.0 * 0.1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn dot_k_zero_folds() {
+        dot_k(&[1.0], &[1.0], 0);
+    }
+
+    #[test]
+    fn dot_exact_vs_exact_sum() {
+        use rand::prelude::*;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+        for _ in 0..20 {
+            let (x, y) = ill_conditioned(&mut rng, 50, 100);
+            let mut sum = ExactSum::new();
+            for (&a, &b) in x.iter().zip(&y) {
+                let (p, e) = two_prod(a, b);
+                sum += e;
+                sum += p;
+            }
+            assert_eq!(dot_exact(&x, &y), sum.total());
+        }
+    }
+
+    #[test]
+    fn dot_accumulator_sub() {
+        let mut dot = DotAccumulator::new();
+        dot += (0.1, 3.0);
+        dot -= (0.3, 1.0);
+        assert_eq!(
+            dot,
+            [(0.1, 3.0), (-0.3, 1.0)]
+                .into_iter()
+                .sum::<DotAccumulator<f64>>()
+        );
+    }
+}
@@ -0,0 +1,248 @@
+use crate::*;
+
+/// This type is an accumulator for computing exponentially weighted moving sums and averages.
+///
+/// The generic parameter `T` should typically implement [`num_traits::float::Float`] and can usually be inferred.
+///
+/// With a decay factor $\alpha$, each unit of time multiplies the weight of the past values by $\lambda=1-\alpha$,
+/// so that the decayed sum and weight are $S=\sum_i\lambda^{t-t_i}x_i$ and $W=\sum_i\lambda^{t-t_i}$,
+/// and the moving average is $S/W$ (which, unlike the usual recurrence $m\leftarrow\lambda m+\alpha x$,
+/// is not biased towards zero at the beginning of the stream).
+/// Both $S$ and $W$ are kept as compensated double-words: each decay is a product computed with [`two_prod`]
+/// and each new value is added with [`two_sum`], hence the state does not drift however long the stream is.
+/// The factor $\lambda$ itself is rounded, hence the weight of a value has a relative error proportional to its age,
+/// which is negligible as long as the weight is not.
+///
+/// Values are added one unit of time apart with `+=`, or after an explicit time delta with [`Ewma::add_after()`],
+/// so that irregularly spaced samples are supported.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::Ewma;
+/// let mut ewma = Ewma::new(0.5);
+/// for x in [1.0, 2.0, 3.0, 4.0] {
+///     ewma += x;
+/// }
+/// assert_eq!(ewma.sum(), 0.125 + 0.5 + 1.5 + 4.0);
+/// assert_eq!(ewma.weight(), 0.125 + 0.25 + 0.5 + 1.0);
+/// assert_eq!(ewma.mean(), ewma.sum() / ewma.weight());
+/// ```
+///
+/// The decay can also be given as a half-life, in units of time.
+///
+/// ```
+/// # use compensated_summation::Ewma;
+/// let mut ewma = Ewma::with_half_life(10.0);
+/// ewma += 2.0;
+/// ewma.add_after(4.0, 10.0);
+/// let x: f64 = ewma.mean();
+/// assert!((x - 10.0 / 3.0).abs() < 1e-15);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ewma<T> {
+    /// Factor $\lambda=1-\alpha$ by which the past is multiplied for each unit of time.
+    factor: T,
+    /// Decayed sum of the values, as a double-word.
+    sum: (T, T),
+    /// Decayed sum of the weights, as a double-word.
+    weight: (T, T),
+}
+
+impl<T: Float> Ewma<T> {
+    /// Create a new empty accumulator with decay factor $\alpha$.
+    ///
+    /// # Panics
+    ///
+    /// Panics if $\alpha$ is not in $(0,1]$.
+    pub fn new(alpha: T) -> Self {
+        assert!(
+            alpha > T::zero() && alpha <= T::one(),
+            "decay factor must be in (0, 1]"
+        );
+        Self {
+            factor: T::one() - alpha,
+            sum: (T::zero(), T::zero()),
+            weight: (T::zero(), T::zero()),
+        }
+    }
+
+    /// Create a new empty accumulator whose weights halve every `half_life` units of time,
+    /// that is with $\lambda=2^{-1/h}$.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the half-life is not positive.
+    pub fn with_half_life(half_life: T) -> Self {
+        assert!(half_life > T::zero(), "half-life must be positive");
+        Self::new(T::one() - (-half_life.recip()).exp2())
+    }
+
+    /// Let `dt` units of time pass without adding any value.
+    ///
+    /// This multiplies both the sum and the weight by $\lambda^{dt}$, hence the mean is unchanged.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dt` is negative.
+    pub fn decay(&mut self, dt: T) {
+        assert!(dt >= T::zero(), "time delta must be nonnegative");
+        let f = if dt == T::one() {
+            self.factor
+        } else {
+            self.factor.powf(dt)
+        };
+        self.sum = mul_dw(self.sum, f);
+        self.weight = mul_dw(self.weight, f);
+    }
+
+    /// Add a value `dt` units of time after the previous one.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dt` is negative.
+    pub fn add_after(&mut self, x: T, dt: T) {
+        self.decay(dt);
+        self.sum = add_dw(self.sum, x);
+        self.weight = add_dw(self.weight, T::one());
+    }
+
+    /// Get the estimated decayed sum $S$.
+    pub fn sum(&self) -> T {
+        self.sum.0 + self.sum.1
+    }
+
+    /// Get the estimated decayed weight $W$.
+    pub fn weight(&self) -> T {
+        self.weight.0 + self.weight.1
+    }
+
+    /// Get the moving average $S/W$.
+    ///
+    /// This is NaN if the accumulator is empty.
+    pub fn mean(&self) -> T {
+        self.sum() / self.weight()
+    }
+}
+
+impl<T: Float> AddAssign<T> for Ewma<T> {
+    fn add_assign(&mut self, rhs: T) {
+        self.add_after(rhs, T::one());
+    }
+}
+
+impl<T: Float> AddAssign<&T> for Ewma<T> {
+    fn add_assign(&mut self, rhs: &T) {
+        *self += *rhs;
+    }
+}
+
+impl<T: Float, V> Add<V> for Ewma<T>
+where
+    Self: AddAssign<V>,
+{
+    type Output = Self;
+    fn add(mut self, rhs: V) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn ewma_vs_f64() {
+        use rand::prelude::*;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        // With an exact factor, the `f64` recurrence is a reference for `f32`.
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+        let alpha = 2f32.powi(-12);
+        let factor = (1.0 - alpha) as f64;
+        let mut ewma = Ewma::new(alpha);
+        let (mut naive_sum, mut naive_weight) = (0f32, 0f32);
+        let (mut sum, mut weight) = (0f64, 0f64);
+        for _ in 0..100_000 {
+            let x: f32 = rng.gen_range(0.0..1.0);
+            ewma += x;
+            naive_sum = naive_sum * (1.0 - alpha) + x;
+            naive_weight = naive_weight * (1.0 - alpha) + 1.0;
+            sum = sum * factor + x as f64;
+            weight = weight * factor + 1.0;
+        }
+        let (sum, weight) = (sum as f32, weight as f32);
+        assert!((ewma.sum() - sum).abs() <= sum * f32::EPSILON / 2.0);
+        assert!((ewma.weight() - weight).abs() <= weight * f32::EPSILON / 2.0);
+        assert!((naive_sum - sum).abs() > 100.0 * (ewma.sum() - sum).abs());
+        assert!((naive_weight - weight).abs() > 100.0 * (ewma.weight() - weight).abs());
+    }
+
+    #[test]
+    fn ewma_irregular() {
+        use rand::prelude::*;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+        let half_life = 100.0;
+        let mut ewma = Ewma::with_half_life(half_life);
+        let mut samples = Vec::new();
+        let mut t = 0.0;
+        for _ in 0..100_000 {
+            let x: f64 = rng.gen_range(-1.0..1.0);
+            // Steps on a grid of `2^-20`, so that the times are exact.
+            let dt = rng.gen_range(0..3 << 20) as f64 * 2f64.powi(-20);
+            ewma.add_after(x, dt);
+            t += dt;
+            samples.push((x, t));
+        }
+        // Reference with the exact weights `2^(-(t - t_i)/h)`, which are negligible beyond 64 half-lives.
+        let n = samples.len();
+        let recent = samples
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| t - s.1 <= 64.0 * half_life);
+        let mut sum = ExactSum::new();
+        let mut weight = ExactSum::new();
+        let mut tol = 0.0;
+        for (i, &(x, ti)) in recent {
+            let age = t - ti;
+            let decays = n - 1 - i;
+            let w = (-age / half_life).exp2();
+            let (p, δp) = two_prod(w, x);
+            sum += p;
+            sum += δp;
+            weight += w;
+            // The rounding of the factor compounds over the age, and the rounding of each power over the decays.
+            tol += w * (2.0 + age + decays as f64) * f64::EPSILON;
+        }
+        let (sum, weight) = (sum.total(), weight.total());
+        assert!((ewma.sum() - sum).abs() <= tol);
+        assert!((ewma.weight() - weight).abs() <= tol);
+    }
+
+    #[test]
+    fn ewma_decay() {
+        let mut ewma = Ewma::new(1.0);
+        assert!(ewma.mean().is_nan());
+        ewma += 3.0;
+        ewma += 5.0;
+        assert_eq!(ewma.sum(), 5.0);
+        assert_eq!(ewma.mean(), 5.0);
+
+        let mut ewma = Ewma::with_half_life(2.0) + 1.0 + 3.0;
+        let mean = ewma.mean();
+        ewma.decay(0.0);
+        assert_eq!(ewma.mean(), mean);
+        ewma.decay(4.0);
+        assert!((ewma.mean() - mean).abs() <= mean * f64::EPSILON);
+        assert!((ewma.weight() - (1.0 + 0.5f64.sqrt()) / 4.0).abs() <= f64::EPSILON);
+    }
+
+    #[test]
+    #[should_panic]
+    fn ewma_invalid_alpha() {
+        Ewma::new(0.0f64);
+    }
+}
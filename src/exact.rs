@@ -0,0 +1,295 @@
+use crate::*;
+
+/// This type is an accumulator for computing the **correctly rounded** sum of floating-point numbers.
+///
+/// The exact sum is represented as a nonoverlapping expansion, that is, a list of floating-point
+/// numbers of increasing magnitude whose exact mathematical sum is the exact sum of all the
+/// values added so far (see [Shewchuk (1997)](https://doi.org/10.1007/PL00009321)).
+/// Each addition is performed with [`fast_two_sum`], after ordering the operands by magnitude,
+/// and the final total is rounded to nearest exactly once, like Python's
+/// [`math.fsum`](https://docs.python.org/3/library/math.html#math.fsum).
+///
+/// Contrary to [`KahanBabuska`] and [`KahanBabuskaNeumaier`], this accumulator needs a heap
+/// allocation, and each addition costs time proportional to the number of components of the
+/// expansion (which is small in practice).
+///
+/// Non-finite inputs are summed separately, so that the total is infinite or NaN as expected.
+/// An overflow of the *partial* sums, however, makes the total non-finite even if the exact sum
+/// would be representable.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::ExactSum;
+/// let mut sum = ExactSum::new();
+/// sum += 1e100;
+/// sum += 1.0;
+/// sum -= 1e100;
+/// sum += 1e-100;
+/// assert_eq!(sum.total(), 1.0);
+/// ```
+///
+/// [`ExactSum`] also implements the [`std::iter::Sum`](#impl-Sum<V>-for-ExactSum<T>) trait.
+///
+/// ```
+/// # use compensated_summation::ExactSum;
+/// let iter = [0.1, 0.2, -0.3].iter();
+/// assert_eq!(iter.sum::<ExactSum<_>>().total(), f64::EPSILON / 8.0);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExactSum<T> {
+    /// Nonoverlapping components, in order of increasing magnitude.
+    partials: Vec<T>,
+    /// Naive sum of the non-finite inputs.
+    special: T,
+}
+
+impl<T: Float> ExactSum<T> {
+    /// Create a new empty accumulator.
+    pub fn new() -> Self {
+        Self {
+            partials: Vec::new(),
+            special: T::zero(),
+        }
+    }
+
+    /// Get the nonoverlapping components of the expansion, in order of increasing magnitude.
+    ///
+    /// Their exact mathematical sum is the exact sum of all the (finite) values added so far.
+    pub fn components(&self) -> &[T] {
+        &self.partials
+    }
+
+    /// Get the exact sum, correctly rounded to nearest.
+    pub fn total(&self) -> T {
+        if self.special != T::zero() {
+            return self.special;
+        }
+        let mut n = self.partials.len();
+        if n == 0 {
+            return T::zero();
+        }
+        n -= 1;
+        let mut hi = self.partials[n];
+        let mut lo = T::zero();
+        while n > 0 {
+            n -= 1;
+            let (s, t) = fast_two_sum(hi, self.partials[n]);
+            hi = s;
+            lo = t;
+            if lo != T::zero() {
+                break;
+            }
+        }
+        // Correct the rounding in the halfway case, when the rest of the expansion has the same sign of `lo`.
+        if n > 0
+            && ((lo < T::zero() && self.partials[n - 1] < T::zero())
+                || (lo > T::zero() && self.partials[n - 1] > T::zero()))
+        {
+            let y = lo + lo;
+            let x = hi + y;
+            if x - hi == y {
+                hi = x;
+            }
+        }
+        hi
+    }
+
+    /// Merge another accumulator into this one, as if all its values had been added to this one.
+    pub fn merge(&mut self, other: &Self) {
+        for &x in &other.partials {
+            *self += x;
+        }
+        self.special = self.special + other.special;
+    }
+}
+
+impl<T: Float> Default for ExactSum<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> Accumulator for ExactSum<T> {
+    type Output = T;
+    fn total(&self) -> T {
+        self.total()
+    }
+    fn merge(&mut self, other: &Self) {
+        self.merge(other)
+    }
+}
+
+impl<T: Float> AddAssign<T> for ExactSum<T> {
+    fn add_assign(&mut self, rhs: T) {
+        if !rhs.is_finite() {
+            self.special = self.special + rhs;
+            return;
+        }
+        let mut x = rhs;
+        let mut i = 0;
+        for j in 0..self.partials.len() {
+            let mut y = self.partials[j];
+            if x.abs() < y.abs() {
+                std::mem::swap(&mut x, &mut y);
+            }
+            let (hi, lo) = fast_two_sum(x, y);
+            if lo != T::zero() {
+                self.partials[i] = lo;
+                i += 1;
+            }
+            x = hi;
+        }
+        self.partials.truncate(i);
+        if x != T::zero() {
+            self.partials.push(x);
+        }
+    }
+}
+
+impl<T: Float> SubAssign<T> for ExactSum<T> {
+    fn sub_assign(&mut self, rhs: T) {
+        *self += -rhs;
+    }
+}
+
+impl<T: Float> AddAssign<&T> for ExactSum<T> {
+    fn add_assign(&mut self, rhs: &T) {
+        *self += *rhs;
+    }
+}
+
+impl<T: Float> SubAssign<&T> for ExactSum<T> {
+    fn sub_assign(&mut self, rhs: &T) {
+        *self -= *rhs;
+    }
+}
+
+impl<T: Float, V> Add<V> for ExactSum<T>
+where
+    Self: AddAssign<V>,
+{
+    type Output = Self;
+    fn add(mut self, rhs: V) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl<T: Float, V> Sub<V> for ExactSum<T>
+where
+    Self: SubAssign<V>,
+{
+    type Output = Self;
+    fn sub(mut self, rhs: V) -> Self::Output {
+        self -= rhs;
+        self
+    }
+}
+
+impl<T: Float, V> Sum<V> for ExactSum<T>
+where
+    Self: AddAssign<V>,
+{
+    fn sum<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = V>,
+    {
+        let mut sum = ExactSum::new();
+        for x in iter {
+            sum += x;
+        }
+        sum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn exact_large() {
+        assert_eq!(
+            [1.0, 1e100, 1.0, -1e100]
+                .iter()
+                .sum::<ExactSum<f64>>()
+                .total(),
+            2.0
+        );
+    }
+
+    #[test]
+    fn exact_halfway() {
+        // 1 + 2^-53 is halfway between 1 and 1 + 2^-52: the tiny term decides the rounding.
+        let h = f64::EPSILON / 2.0;
+        assert_eq!(
+            [1.0, h, 1e-300].iter().sum::<ExactSum<f64>>().total(),
+            1.0 + 2.0 * h
+        );
+        assert_eq!([1.0, h, -1e-300].iter().sum::<ExactSum<f64>>().total(), 1.0);
+        assert_eq!([1.0, h].iter().sum::<ExactSum<f64>>().total(), 1.0);
+    }
+
+    #[test]
+    fn exact_special() {
+        assert_eq!(
+            [1.0, f64::INFINITY].iter().sum::<ExactSum<f64>>().total(),
+            f64::INFINITY
+        );
+        assert!([f64::INFINITY, f64::NEG_INFINITY]
+            .iter()
+            .sum::<ExactSum<f64>>()
+            .total()
+            .is_nan());
+    }
+
+    #[test]
+    fn exact_empty() {
+        assert_eq!(ExactSum::<f64>::new().total(), 0.0);
+        assert!(ExactSum::<f64>::new().components().is_empty());
+    }
+
+    #[test]
+    fn test_exact_vs_neumaier() {
+        use rand::prelude::*;
+        use rand_distr::Normal;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        // Well-conditioned sums: Kahan-Babuška-Neumaier is already faithful, and usually correct.
+        for seed in 0..100 {
+            let rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+            let values: Vec<f64> = rng
+                .sample_iter(Normal::new(10.0, 1.0).unwrap())
+                .take(1_000)
+                .collect();
+
+            let exact = values.iter().sum::<ExactSum<_>>().total();
+            let neumaier = values.iter().sum::<KahanBabuskaNeumaier<_>>().total();
+            assert!((exact - neumaier).abs() <= exact.abs() * f64::EPSILON);
+        }
+    }
+
+    #[test]
+    fn test_exact_cancellation() {
+        use rand::prelude::*;
+        use rand_distr::LogNormal;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        // Adding and then subtracting all the values in a different order cancels exactly.
+        for seed in 0..10 {
+            let mut rng = Xoshiro256PlusPlus::seed_from_u64(seed);
+            let mut values: Vec<f64> = (&mut rng)
+                .sample_iter(LogNormal::new(0.0, 40.0).unwrap())
+                .take(1_000)
+                .collect();
+            let mut sum: ExactSum<f64> = values.iter().sum();
+            sum += 0.5;
+            values.shuffle(&mut rng);
+            for x in &values {
+                sum -= x;
+            }
+            assert_eq!(sum.total(), 0.5);
+            assert_eq!(sum.components(), [0.5]);
+        }
+    }
+}
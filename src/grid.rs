@@ -0,0 +1,204 @@
+use crate::*;
+
+/// Iterator over evenly spaced floating-point numbers.
+///
+/// This `struct` is created by the [`linspace()`] and [`arange()`] functions.
+///
+/// The current element and the step are kept as double-words, and each element is obtained
+/// by adding the step to the previous one with [`two_sum`], so that the elements are
+/// (almost always) the correctly rounded values of $x_0+ih$ and never drift away from them.
+/// Since the exact values are monotonic and rounding to nearest is monotonic, so are the elements.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Grid<T> {
+    /// Next element, as a double-word.
+    next: (T, T),
+    /// Step, as a double-word.
+    step: (T, T),
+    /// Number of remaining elements.
+    len: usize,
+    /// Exact value of the last element, if known.
+    end: Option<T>,
+}
+
+impl<T: Float> Iterator for Grid<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        let x = match self.end {
+            Some(end) if self.len == 0 => end,
+            _ => self.next.0 + self.next.1,
+        };
+        self.next = add_dw(add_dw(self.next, self.step.0), self.step.1);
+        Some(x)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T: Float> ExactSizeIterator for Grid<T> {}
+
+/// Evenly spaced numbers over an interval, like NumPy's `linspace`.
+///
+/// **Input:** the endpoints $a$ and $b$ of the interval, and the number $n$ of elements.
+///
+/// **Output:** an iterator over $x_i=a+i(b-a)/(n-1)$ for $0\leq i<n$, with $x_0=a$ and $x_{n-1}=b$ exactly
+/// (a single element is $a$).
+///
+/// The difference $b-a$ is computed exactly with [`two_sum`] and the step is divided as a double-word
+/// (if the difference overflows, the step is computed as $b/(n-1)-a/(n-1)$ instead), so that the elements are accurate even when $a$ and $b$ are close relatively to their magnitude.
+/// The elements are monotonic (nondecreasing if $a\leq b$, nonincreasing otherwise).
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::linspace;
+/// let x: Vec<f64> = linspace(0.0, 1.0, 11).collect();
+/// assert_eq!(x, [0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 1.0]);
+/// assert_ne!(3.0 * 0.1, 0.3);
+/// ```
+pub fn linspace<T: Float>(a: T, b: T, n: usize) -> Grid<T> {
+    let step = if n > 1 {
+        let m = T::from(n - 1).unwrap();
+        let (d, δd) = two_sum(b, -a);
+        if d.is_finite() {
+            let (h, l) = div_dw((d, δd), m);
+            fast_two_sum(h, l)
+        } else {
+            // The endpoints have opposite signs, and are divided before the subtraction.
+            let (qb, rb) = div_dw((b, T::zero()), m);
+            let (qa, ra) = div_dw((a, T::zero()), m);
+            let (h, l) = two_sum(qb, -qa);
+            fast_two_sum(h, l + (rb - ra))
+        }
+    } else {
+        (T::zero(), T::zero())
+    };
+    Grid {
+        next: (a, T::zero()),
+        step,
+        len: n,
+        end: if n > 1 { Some(b) } else { None },
+    }
+}
+
+/// Evenly spaced numbers with a given step, like NumPy's `arange` but with a number of elements instead of an endpoint.
+///
+/// **Input:** the first element $x_0$, the step $h$ and the number $n$ of elements.
+///
+/// **Output:** an iterator over $x_i=x_0+ih$ for $0\leq i<n$.
+///
+/// The elements are monotonic (nondecreasing if $h\geq0$, nonincreasing otherwise).
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::arange;
+/// let last = arange(0.0, 0.1, 1_000_001).last();
+/// assert_eq!(last, Some(100_000.0));
+/// assert_ne!([0.1; 1_000_000].iter().sum::<f64>(), 100_000.0);
+/// ```
+pub fn arange<T: Float>(start: T, step: T, n: usize) -> Grid<T> {
+    Grid {
+        next: (start, T::zero()),
+        step: (step, T::zero()),
+        len: n,
+        end: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn linspace_vs_f64() {
+        use rand::prelude::*;
+        use rand_distr::LogNormal;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+        let dist = LogNormal::new(0.0, 2.0).unwrap();
+        for _ in 0..100 {
+            let a: f32 = rng.sample(dist) * if rng.gen() { 1.0 } else { -1.0 };
+            let b: f32 = a + rng.sample(dist) * if rng.gen() { 1.0 } else { -1.0 };
+            let n = rng.gen_range(2..10_000);
+            let x: Vec<f32> = linspace(a, b, n).collect();
+            assert_eq!(x.len(), n);
+            assert_eq!(x[0], a);
+            assert_eq!(x[n - 1], b);
+            // Each element is rounded once, up to the accumulated error of the double-words.
+            let h = (b as f64 - a as f64) / (n - 1) as f64;
+            let eps = f32::EPSILON as f64;
+            let drift = a.abs().max(b.abs()) as f64 * eps * eps * n as f64;
+            for (i, &xi) in x.iter().enumerate() {
+                let r = a as f64 + i as f64 * h;
+                assert!((xi as f64 - r).abs() <= r.abs() * eps / 2.0 + drift);
+            }
+            if a <= b {
+                assert!(x.windows(2).all(|w| w[0] <= w[1]));
+            } else {
+                assert!(x.windows(2).all(|w| w[0] >= w[1]));
+            }
+        }
+    }
+
+    #[test]
+    fn linspace_close_endpoints() {
+        // Steps much smaller than the ulp of the endpoints.
+        let a = 1.0;
+        let b = 1.0 + 4.0 * f64::EPSILON;
+        let x: Vec<f64> = linspace(a, b, 9).collect();
+        let ulp = f64::EPSILON;
+        // Ties are rounded to even.
+        assert_eq!(
+            x,
+            [
+                a,
+                a,
+                a + ulp,
+                a + 2.0 * ulp,
+                a + 2.0 * ulp,
+                a + 2.0 * ulp,
+                a + 3.0 * ulp,
+                a + 4.0 * ulp,
+                b
+            ]
+        );
+        assert_eq!(linspace(1.0, 2.0, 0).next(), None);
+        assert_eq!(linspace(1.0, 2.0, 1).collect::<Vec<_>>(), [1.0]);
+        assert_eq!(linspace(1.0, 2.0, 2).collect::<Vec<_>>(), [1.0, 2.0]);
+        assert_eq!(linspace(2.0, 1.0, 3).collect::<Vec<_>>(), [2.0, 1.5, 1.0]);
+    }
+
+    #[test]
+    fn linspace_extreme_endpoints() {
+        // The difference of the endpoints overflows.
+        let x: Vec<f64> = linspace(-f64::MAX, f64::MAX, 3).collect();
+        assert_eq!(x, [-f64::MAX, 0.0, f64::MAX]);
+        let x: Vec<f64> = linspace(f64::MAX, -f64::MAX, 5).collect();
+        assert_eq!(
+            x,
+            [f64::MAX, f64::MAX / 2.0, 0.0, -f64::MAX / 2.0, -f64::MAX]
+        );
+        let x: Vec<f32> = linspace(-f32::MAX, f32::MAX, 2).collect();
+        assert_eq!(x, [-f32::MAX, f32::MAX]);
+    }
+
+    #[test]
+    fn arange_no_drift() {
+        for step in [0.1f32, -1.0 / 3.0, 1e-3] {
+            let n = 1_000_000;
+            let grid = arange(1.0, step, n);
+            assert_eq!(grid.len(), n);
+            for (i, x) in grid.enumerate().step_by(997) {
+                assert_eq!(x, (1.0 + i as f64 * step as f64) as f32);
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use crate::*;
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// This type is a collection of accumulators indexed by keys, for computing sums grouped by key.
+///
+/// The generic parameter `K` is the type of the keys and `A` is the type of the accumulators,
+/// for example [`KahanBabuskaNeumaier`] or [`ExactSum`] (any [`Accumulator`] can be used).
+/// The accumulator of a key is created the first time a value is added for it.
+///
+/// Grouped sums computed separately (for example, on different threads) can be merged with [`GroupedSum::merge()`].
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::{GroupedSum, KahanBabuskaNeumaier};
+/// let mut sums = GroupedSum::<&str, KahanBabuskaNeumaier<f64>>::new();
+/// sums.add("a", 0.1);
+/// sums.add("b", 1.0);
+/// sums.add("a", 0.2);
+/// sums.add("a", -0.3);
+/// assert_eq!(sums.total("a"), Some(f64::EPSILON / 8.0));
+/// assert_eq!(sums.total("b"), Some(1.0));
+/// assert_eq!(sums.total("c"), None);
+/// ```
+///
+/// [`GroupedSum`] also implements the [`std::iter::FromIterator`](#impl-FromIterator<(K,+V)>-for-GroupedSum<K,+A>) trait,
+/// hence an iterator of `(key, value)` pairs can be collected into grouped sums.
+///
+/// ```
+/// # use compensated_summation::{ExactSum, GroupedSum};
+/// let sums: GroupedSum<_, ExactSum<f64>> = [(1, 1e100), (2, 1.0), (1, 1.0), (1, -1e100)]
+///     .into_iter()
+///     .collect();
+/// let mut totals: Vec<_> = sums.iter().collect();
+/// totals.sort_by_key(|&(k, _)| *k);
+/// assert_eq!(totals, [(&1, 1.0), (&2, 1.0)]);
+/// ```
+#[derive(Clone, Debug)]
+pub struct GroupedSum<K, A> {
+    /// Accumulator of each key.
+    groups: HashMap<K, A>,
+}
+
+impl<K: Eq + Hash, A: Accumulator> GroupedSum<K, A> {
+    /// Create a new empty collection.
+    pub fn new() -> Self {
+        Self {
+            groups: HashMap::new(),
+        }
+    }
+
+    /// Get the number of keys.
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Whether no value has been added.
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Add a value to the accumulator of the given key.
+    pub fn add<V>(&mut self, key: K, value: V)
+    where
+        A: AddAssign<V>,
+    {
+        *self.groups.entry(key).or_default() += value;
+    }
+
+    /// Get the accumulator of the given key, if any value has been added for it.
+    pub fn get<Q>(&self, key: &Q) -> Option<&A>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.groups.get(key)
+    }
+
+    /// Get the estimated total of the given key, if any value has been added for it.
+    pub fn total<Q>(&self, key: &Q) -> Option<A::Output>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.groups.get(key).map(A::total)
+    }
+
+    /// Iterate over the keys and their estimated totals, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, A::Output)> + '_ {
+        self.groups.iter().map(|(k, a)| (k, a.total()))
+    }
+
+    /// Iterate over the keys and their accumulators, in arbitrary order.
+    pub fn accumulators(&self) -> impl Iterator<Item = (&K, &A)> + '_ {
+        self.groups.iter()
+    }
+
+    /// Merge other grouped sums into these ones, as if all their values had been added to these ones.
+    pub fn merge(&mut self, other: &Self)
+    where
+        K: Clone,
+        A: Clone,
+    {
+        for (k, a) in &other.groups {
+            match self.groups.get_mut(k) {
+                Some(acc) => acc.merge(a),
+                None => {
+                    self.groups.insert(k.clone(), a.clone());
+                }
+            }
+        }
+    }
+}
+
+impl<K: Eq + Hash, A: Accumulator> Default for GroupedSum<K, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Eq + Hash, A: Accumulator + AddAssign<V>, V> Extend<(K, V)> for GroupedSum<K, A> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.add(k, v);
+        }
+    }
+}
+
+impl<K: Eq + Hash, A: Accumulator + AddAssign<V>, V> FromIterator<(K, V)> for GroupedSum<K, A> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut sums = GroupedSum::new();
+        sums.extend(iter);
+        sums
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn grouped_vs_exact() {
+        use rand::prelude::*;
+        use rand_distr::LogNormal;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+        let dist = LogNormal::new(0.0, 10.0).unwrap();
+        let pairs: Vec<(String, f64)> = (0..10_000)
+            .map(|_| {
+                let key = format!("key{}", rng.gen_range(0..10));
+                let x = rng.sample(dist) * if rng.gen() { 1.0 } else { -1.0 };
+                (key, x)
+            })
+            .collect();
+
+        let exact: GroupedSum<String, ExactSum<f64>> = pairs.iter().cloned().collect();
+        assert_eq!(exact.len(), 10);
+        for (key, total) in exact.iter() {
+            let values = pairs.iter().filter(|(k, _)| k == key).map(|(_, x)| x);
+            assert_eq!(total, values.sum::<ExactSum<f64>>().total());
+        }
+
+        // Merging partial results gives the same totals.
+        let mut merged = GroupedSum::<String, ExactSum<f64>>::new();
+        for chunk in pairs.chunks(1000) {
+            merged.merge(&chunk.iter().map(|(k, x)| (k.clone(), x)).collect());
+        }
+        assert_eq!(merged.len(), exact.len());
+        for (key, total) in merged.iter() {
+            assert_eq!(exact.total(key.as_str()), Some(total));
+        }
+
+        let kbn: GroupedSum<&str, KahanBabuskaNeumaier<f64>> =
+            pairs.iter().map(|(k, x)| (k.as_str(), x)).collect();
+        for (key, acc) in kbn.accumulators() {
+            let values = pairs.iter().filter(|(k, _)| k == key).map(|(_, x)| x);
+            assert_eq!(acc, &values.sum());
+            assert!(exact.get(*key).is_some());
+        }
+    }
+
+    #[test]
+    fn grouped_accumulators() {
+        let mut norms = GroupedSum::<char, Norm2Accumulator<f64>>::new();
+        assert!(norms.is_empty());
+        norms.extend([('x', 3.0), ('y', 1e300), ('x', 4.0), ('y', 1e300)]);
+        let mut other = GroupedSum::new();
+        other.add('y', 1e300);
+        other.add('y', 1e300);
+        other.add('z', 1.0);
+        norms.merge(&other);
+        assert_eq!(norms.total(&'x'), Some(5.0));
+        assert_eq!(norms.total(&'y'), Some(2e300));
+        assert_eq!(norms.total(&'z'), Some(1.0));
+
+        let dots: GroupedSum<u8, DotAccumulator<f64>> =
+            [(0, (1e100, 1.0)), (0, (1.0, 1.0)), (0, (-1e100, 1.0))]
+                .into_iter()
+                .collect();
+        assert_eq!(dots.total(&0), Some(1.0));
+    }
+}
@@ -8,6 +8,11 @@
 
 Both functions return a tuple `(s, t)` where `s` is the floating-point sum rounded to nearest and `t` is the floating-point error.
 
+Similarly, [`two_prod()`] computes the rounded product of two floating-point numbers and the associated numerical error.
+
+[`diff_of_products()`] and [`sum_of_products()`] compute $ab\mp cd$ accurately with Kahan's algorithm,
+and [`det2()`] and [`det3()`] compute correctly rounded determinants of small matrices.
+
 #### Compensated summation
 
 [`KahanBabuska`] and [`KahanBabuskaNeumaier`] allow to compute compensated sums using the [Kahan-Babuška](https://en.wikipedia.org/wiki/Kahan_summation_algorithm#The_algorithm) and [Kahan-Babuška-Neumaier](https://en.wikipedia.org/wiki/Kahan_summation_algorithm#Further_enhancements) algorithms respectively.
@@ -19,6 +24,106 @@ The estimated total sum (of type `T`) can be retrieved with a method called `tot
 
 Both types also implement [`std::iter::Sum`], which means that iterators of floating-point numbers can be conveniently summed.
 
+#### Compensated product
+
+[`CompensatedProduct`] computes products (and quotients) of many factors with the compensated product algorithm of
+Graillat, keeping track of the exponent separately so that it never overflows or underflows.
+It implements [`std::iter::Product`].
+
+#### Exact summation
+
+[`ExactSum`] represents the exact sum of floating-point numbers as a nonoverlapping expansion,
+and its `total()` is the correctly rounded sum (at the cost of a heap allocation).
+
+#### Compensated dot product
+
+[`dot2()`] computes the dot product of two slices as if in twice the working precision, using the `Dot2` algorithm of
+[Ogita, Rump and Oishi](https://doi.org/10.1137/030601818).
+The same algorithm is available in streaming form as the [`DotAccumulator`] type, which accumulates pairs of floating-point numbers.
+
+For extremely ill-conditioned problems, [`dot_k()`] computes the dot product as if in $K$-fold working precision,
+and [`dot_exact()`] returns the correctly rounded dot product.
+
+[`gemv_compensated()`] and [`gemm_compensated()`] compute matrix-vector and matrix-matrix products of dense matrices
+stored in slices (see [`Layout`]), where each element of the result is a compensated dot product.
+
+#### Compensated polynomial evaluation
+
+[`comp_horner()`] evaluates a polynomial with the compensated Horner scheme of
+Graillat, Langlois and Louvet, which is as accurate as the Horner scheme
+in twice the working precision, even near multiple roots.
+The variants [`comp_horner_dw()`] and [`comp_horner_with_bound()`] return the result as a double-word
+and together with an a posteriori error bound, respectively.
+
+Similarly, [`comp_clenshaw_chebyshev()`] evaluates Chebyshev series with a compensated Clenshaw recurrence,
+and [`comp_clenshaw()`] does the same for series of any family of functions defined by a three-term recurrence.
+
+Polynomials in Bernstein form and Bézier curves can be evaluated with the compensated de Casteljau algorithm
+by [`comp_de_casteljau()`] and [`comp_de_casteljau_points()`].
+
+#### Euclidean norm
+
+[`norm2()`] and the streaming [`Norm2Accumulator`] compute the Euclidean norm with exact power-of-two scaling,
+so that no overflow or underflow occurs for finite inputs, and with compensated accumulation of the squares.
+[`hypot_dw()`] computes the Euclidean distance of two double-words.
+
+[`SumOfSquares`] accumulates squares without scaling, compensating both the error of each product and of each addition;
+it also provides the root mean square and can be merged with other accumulators.
+
+#### Statistics
+
+[`Moments`] computes count, mean, variance and standard deviation in one pass, with a compensated Welford update
+that remains accurate when the mean is large compared with the spread; accumulators can be merged for parallel use.
+[`HigherMoments`] additionally tracks the third and fourth central moments, providing skewness and kurtosis.
+[`Bivariate`] accumulates pairs of floating-point numbers, providing covariance, correlation and simple linear regression.
+[`WeightedSum`] accumulates `(value, weight)` pairs, providing the weighted sum, mean and variance.
+
+[`Ewma`] computes exponentially weighted moving sums and averages, with a decay factor or a half-life,
+keeping its state as compensated double-words so that long-running streams do not drift;
+irregularly spaced samples are supported through explicit time deltas.
+[`SlidingWindowSum`] computes the sum, mean and variance of the last values of a stream,
+adding and evicting values exactly so that the total never drifts.
+
+[`LogSumExp`] computes $\ln\sum_ie^{x_i}$ of a stream, such as a sum of likelihoods in log space,
+keeping a running maximum so that no term overflows and a compensated sum of the rescaled exponentials.
+
+#### Prefix sums
+
+[`CompensatedScanExt::compensated_scan()`] is an iterator adaptor yielding the running totals of an iterator,
+and [`cumsum_in_place()`] replaces the elements of a slice with their prefix sums.
+Both use [`KahanBabuskaNeumaier`] by default, and any other [`Accumulator`] (such as [`ExactSum`]) on demand.
+[`par_cumsum_in_place()`] computes the same prefix sums with several threads, propagating the carries between chunks as double-words.
+
+#### Grouped sums
+
+[`GroupedSum`] maps keys to accumulators of any [`Accumulator`] type, for group-by aggregations;
+grouped sums can be collected from iterators of `(key, value)` pairs and merged.
+
+#### Simulation time
+
+[`CompensatedClock`] advances by many small time steps without drifting from their exact sum,
+and compares exactly with event times.
+[`linspace()`] and [`arange()`] generate evenly spaced grids, accumulating the step as a double-word
+so that the elements do not drift, are monotonic, and (for [`linspace()`]) end exactly at the endpoint.
+
+#### Numerical quadrature
+
+[`trapezoid()`], [`trapezoid_xy()`] and [`simpson()`] integrate sampled data with the trapezoidal and Simpson's rules,
+accumulating the weighted samples with compensation and computing the widths of the intervals exactly,
+so that the result is affected by the discretization error only.
+
+#### Differential equations
+
+[`CompensatedState`] is a state vector whose updates $y\leftarrow y+hk$ are compensated component by component,
+so that the round-off error of explicit integrators does not grow with the number of steps;
+it provides the reference steppers [`CompensatedState::euler_step()`] and [`CompensatedState::rk4_step()`].
+
+#### Angles
+
+[`PhaseAccumulator`] accumulates angle increments as a double-word and reduces the phase modulo the period
+(one turn, or $2\pi$ represented as a double-word) without losing the compensation at each wrap,
+counting the number of complete turns.
+
 # Examples
 
 An empty accumulator for the Kahan-Babuška-Neumaier algorithm can be created with [`KahanBabuskaNeumaier::new()`];
@@ -107,6 +212,56 @@ fn two_sub<T: Float>(a: T, b: T) -> (T, T) {
     (s, t)
 }
 
+// Split a finite nonzero `x` as `m * 2^e` with `1 <= |m| < 2`.
+fn frexp<T: Float>(x: T) -> (T, i64) {
+    let (mantissa, exponent, _) = x.integer_decode();
+    let e = exponent as i64 + 63 - mantissa.leading_zeros() as i64;
+    (ldexp(x, -e), e)
+}
+
+// Compute `x * 2^e`, without intermediate overflow or underflow of the scale factor.
+fn ldexp<T: Float>(mut x: T, e: i64) -> T {
+    let two = T::one() + T::one();
+    let step = two.powi(64);
+    // Beyond this range the result has overflowed or underflowed anyway.
+    let mut e = e.clamp(-4096, 4096) as i32;
+    while e > 64 {
+        x = x * step;
+        e -= 64;
+    }
+    while e < -64 {
+        x = x / step;
+        e += 64;
+    }
+    x * two.powi(e)
+}
+
+// Difference `x - (h + l)` of a number and a double-word, rounded.
+fn sub_dw<T: Float>(x: T, (h, l): (T, T)) -> T {
+    let (d, δd) = two_sum(x, -h);
+    d + (δd - l)
+}
+
+// Add `x` to the double-word `(h, l)`, keeping it normalized.
+fn add_dw<T: Float>((h, l): (T, T), x: T) -> (T, T) {
+    let (s, δs) = two_sum(h, x);
+    fast_two_sum(s, l + δs)
+}
+
+// Multiply the double-word `(h, l)` by `x`, keeping it normalized.
+fn mul_dw<T: Float>((h, l): (T, T), x: T) -> (T, T) {
+    let (p, δp) = two_prod(h, x);
+    fast_two_sum(p, l.mul_add(x, δp))
+}
+
+// Divide the double-word `(h, l)` by `x`, as the rounded quotient `h / x` and a correction (not normalized).
+fn div_dw<T: Float>((h, l): (T, T), x: T) -> (T, T) {
+    let q = h / x;
+    // The remainder of a correctly rounded division is exactly representable.
+    let r = (-q).mul_add(x, h);
+    (q, (r + l) / x)
+}
+
 /// `Fast2Sum` algorithm, see <https://en.wikipedia.org/wiki/2Sum>.
 ///
 /// **Input:** two floating-point numbers $a$ and $b$, of which at least one is zero, or which have normalized exponents $e_a\geq e_b$ (such as when $|a|\geq|b|$).
@@ -119,6 +274,126 @@ pub fn fast_two_sum<T: Float>(a: T, b: T) -> (T, T) {
     (s, δb)
 }
 
+/// `TwoProduct` algorithm, see [Ogita, Rump, Oishi (2005)](https://doi.org/10.1137/030601818).
+///
+/// **Input:** two floating-point numbers $a$ and $b$.
+///
+/// **Output:** a tuple $(p,e)$ where $p=a\otimes b$ is the floating-point product [rounded to nearest](https://en.wikipedia.org/wiki/IEEE_754#Roundings_to_nearest) and $e=ab-(a\otimes b)$ is the floating-point error, so that $ab=p+e$.
+///
+/// The error is computed with a fused multiply-add, hence it is exact as long as $ab$ neither overflows nor underflows.
+pub fn two_prod<T: Float>(a: T, b: T) -> (T, T) {
+    let p = a * b;
+    let e = a.mul_add(b, -p);
+    (p, e)
+}
+
+/// Kahan's algorithm for the difference of two products, see [Jeannerod, Louvet, Muller (2013)](https://doi.org/10.1090/S0025-5718-2013-02679-8).
+///
+/// **Input:** four floating-point numbers $a$, $b$, $c$ and $d$.
+///
+/// **Output:** an approximation of $ab-cd$ with a relative error at most $2u$, where $u$ is the unit roundoff,
+/// even when the two products nearly cancel.
+///
+/// The error of the product $cd$ is computed exactly with a fused multiply-add, like in [`two_prod()`],
+/// and added back after subtracting the rounded product from $ab$ with another fused multiply-add.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::diff_of_products;
+/// let (a, b) = (1e8 + 1.0, 1e8 - 1.0);
+/// assert_eq!(diff_of_products(a, b, 1e8, 1e8), -1.0);
+/// assert_eq!(a * b - 1e8 * 1e8, 0.0);
+/// ```
+pub fn diff_of_products<T: Float>(a: T, b: T, c: T, d: T) -> T {
+    let w = c * d;
+    let e = (-c).mul_add(d, w);
+    let f = a.mul_add(b, -w);
+    f + e
+}
+
+/// Kahan's algorithm for the sum of two products.
+///
+/// **Input:** four floating-point numbers $a$, $b$, $c$ and $d$.
+///
+/// **Output:** an approximation of $ab+cd$ with a relative error at most $2u$, where $u$ is the unit roundoff.
+///
+/// This is [`diff_of_products()`] with the sign of $d$ reversed.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::sum_of_products;
+/// let (a, b) = (1e8 + 1.0, 1e8 - 1.0);
+/// assert_eq!(sum_of_products(a, b, -1e8, 1e8), -1.0);
+/// ```
+pub fn sum_of_products<T: Float>(a: T, b: T, c: T, d: T) -> T {
+    diff_of_products(a, b, c, -d)
+}
+
+/// Correctly rounded determinant of a $2\times2$ matrix.
+///
+/// **Input:** a matrix $\begin{pmatrix}a&b\\c&d\end{pmatrix}$, as an array of rows.
+///
+/// **Output:** the determinant $ad-bc$ rounded to nearest.
+///
+/// The products are split exactly with [`two_prod()`] and the four terms are added with [`ExactSum`],
+/// hence the result is correctly rounded as long as no product overflows or underflows.
+/// In particular, its sign is always correct, as needed by geometric predicates.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::det2;
+/// // Nearly parallel vectors.
+/// assert_eq!(det2([[1e8 + 1.0, 1e8], [1e8, 1e8 - 1.0]]), -1.0);
+/// ```
+pub fn det2<T: Float>(m: [[T; 2]; 2]) -> T {
+    let [[a, b], [c, d]] = m;
+    let (p, δp) = two_prod(a, d);
+    let (q, δq) = two_prod(-b, c);
+    [p, δp, q, δq].iter().sum::<ExactSum<T>>().total()
+}
+
+/// Correctly rounded determinant of a $3\times3$ matrix.
+///
+/// **Input:** a matrix $A$, as an array of rows.
+///
+/// **Output:** the determinant $\det A$ rounded to nearest.
+///
+/// Each of the six terms $\pm a_{0i}a_{1j}a_{2k}$ of the Leibniz formula is expanded exactly into four floating-point numbers
+/// with [`two_prod()`], and the 24 numbers are added with [`ExactSum`],
+/// hence the result is correctly rounded as long as no product overflows or underflows.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::det3;
+/// let m = [[1e8 + 1.0, 1e8, 0.0], [1e8, 1e8 - 1.0, 0.0], [0.0, 0.0, 2.0]];
+/// assert_eq!(det3(m), -2.0);
+/// ```
+pub fn det3<T: Float>(m: [[T; 3]; 3]) -> T {
+    let mut sum = ExactSum::new();
+    for (i, j, k, sign) in [
+        (0, 1, 2, T::one()),
+        (1, 2, 0, T::one()),
+        (2, 0, 1, T::one()),
+        (0, 2, 1, -T::one()),
+        (1, 0, 2, -T::one()),
+        (2, 1, 0, -T::one()),
+    ] {
+        let (p, δp) = two_prod(m[1][j], m[2][k]);
+        let a = sign * m[0][i];
+        let (q, δq) = two_prod(a, p);
+        let (r, δr) = two_prod(a, δp);
+        sum += q;
+        sum += δq;
+        sum += r;
+        sum += δr;
+    }
+    sum.total()
+}
+
 /// This type is an accumulator for computing a sum with [Kahan-Babuška algorithm](https://en.wikipedia.org/wiki/Kahan_summation_algorithm#The_algorithm).
 ///
 /// The generic parameter `T` should typically implement [`num_traits::float::Float`] and can usually be inferred.
@@ -175,6 +450,18 @@ impl<T: Float> KahanBabuska<T> {
     pub fn total(&self) -> T {
         self.sum + self.comp
     }
+
+    /// Merge another accumulator into this one, as if all its values had been added to this one.
+    pub fn merge(&mut self, other: &Self) {
+        *self += other.sum;
+        *self += other.comp;
+    }
+}
+
+impl<T: Float> Default for KahanBabuska<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T: Float> Add<T> for KahanBabuska<T> {
@@ -310,6 +597,19 @@ impl<T: Float> KahanBabuskaNeumaier<T> {
     pub fn total(&self) -> T {
         self.sum + self.comp
     }
+
+    /// Merge another accumulator into this one, as if all its values had been added to this one.
+    pub fn merge(&mut self, other: &Self) {
+        let (s, c) = two_sum(self.sum, other.sum);
+        self.sum = s;
+        self.comp = self.comp + (other.comp + c);
+    }
+}
+
+impl<T: Float> Default for KahanBabuskaNeumaier<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T: Float> Add<T> for KahanBabuskaNeumaier<T> {
@@ -337,6 +637,7 @@ impl<T: Float> Sub<T> for KahanBabuskaNeumaier<T> {
 }
 
 impl<T: Float> SubAssign<T> for KahanBabuskaNeumaier<T> {
+    #[allow(clippy::suspicious_op_assign_impl)]
     fn sub_assign(&mut self, rhs: T) {
         let (s, c) = two_sub(self.sum, rhs);
         self.sum = s;
@@ -395,6 +696,92 @@ pub type KahanBabuška<T> = KahanBabuska<T>;
 /// Same as [`KahanBabuskaNeumaier`], but with correct spelling of the second surname.
 pub type KahanBabuškaNeumaier<T> = KahanBabuskaNeumaier<T>;
 
+/// Common interface of the accumulators whose state is summarized by an estimated total.
+///
+/// It allows generic code, such as [`CompensatedScan`] and [`GroupedSum`], to be parameterized by the summation algorithm.
+/// Values are added to an accumulator with its [`AddAssign`] implementations.
+pub trait Accumulator: Default {
+    /// Type of the total, usually the same floating-point type as the accumulated values.
+    type Output;
+
+    /// Get the estimated total.
+    fn total(&self) -> Self::Output;
+
+    /// Merge another accumulator into this one, as if all its inputs had been added to this one.
+    fn merge(&mut self, other: &Self);
+}
+
+impl<T: Float> Accumulator for KahanBabuska<T> {
+    type Output = T;
+    fn total(&self) -> T {
+        self.total()
+    }
+    fn merge(&mut self, other: &Self) {
+        self.merge(other)
+    }
+}
+
+impl<T: Float> Accumulator for KahanBabuskaNeumaier<T> {
+    type Output = T;
+    fn total(&self) -> T {
+        self.total()
+    }
+    fn merge(&mut self, other: &Self) {
+        self.merge(other)
+    }
+}
+
+mod dot;
+pub use dot::*;
+
+mod exact;
+pub use exact::*;
+
+mod linalg;
+pub use linalg::*;
+
+mod product;
+pub use product::*;
+
+mod poly;
+pub use poly::*;
+
+mod norm;
+pub use norm::*;
+
+mod stats;
+pub use stats::*;
+
+mod ewma;
+pub use ewma::*;
+
+mod window;
+pub use window::*;
+
+mod scan;
+pub use scan::*;
+
+mod grouped;
+pub use grouped::*;
+
+mod clock;
+pub use clock::*;
+
+mod grid;
+pub use grid::*;
+
+mod quad;
+pub use quad::*;
+
+mod ode;
+pub use ode::*;
+
+mod phase;
+pub use phase::*;
+
+mod logsumexp;
+pub use logsumexp::*;
+
 /// This module is for development purposes only!
 ///
 /// It provides additional functions and alternative implementations used in testing and benchmarking.
@@ -445,6 +832,74 @@ mod tests {
         }
     }
 
+    #[test]
+    fn products_vs_exact() {
+        use rand::prelude::*;
+        use rand_distr::LogNormal;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+        let dist = LogNormal::new(0.0, 10.0).unwrap();
+        let mut sample = || -> f64 { rng.sample(dist) * if rng.gen() { 1.0 } else { -1.0 } };
+        for _ in 0..1000 {
+            let (a, b, c) = (sample(), sample(), sample());
+            // Make the products nearly cancel.
+            let d = a * b / c;
+            let exact = det2([[a, c], [d, b]]);
+            let (p, δp) = two_prod(a, b);
+            let (q, δq) = two_prod(c, d);
+            assert_eq!(
+                exact,
+                [p, δp, -q, -δq].iter().sum::<ExactSum<f64>>().total()
+            );
+            let diff = diff_of_products(a, b, c, d);
+            assert!((diff - exact).abs() <= 2.0 * f64::EPSILON * exact.abs());
+            let sum = sum_of_products(a, b, -c, d);
+            assert!((sum - exact).abs() <= 2.0 * f64::EPSILON * exact.abs());
+        }
+    }
+
+    #[test]
+    fn det3_vs_f64() {
+        use rand::prelude::*;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        // The determinant of `f32` matrices with small integer entries is exact in `f64`.
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+        for _ in 0..1000 {
+            let m: [[f32; 3]; 3] = std::array::from_fn(|_| {
+                std::array::from_fn(|_| rng.gen_range(-(1 << 16)..1 << 16) as f32)
+            });
+            let w = m.map(|r| r.map(|x| x as f64));
+            let exact = w[0][0] * (w[1][1] * w[2][2] - w[1][2] * w[2][1])
+                - w[0][1] * (w[1][0] * w[2][2] - w[1][2] * w[2][0])
+                + w[0][2] * (w[1][0] * w[2][1] - w[1][1] * w[2][0]);
+            assert_eq!(det3(m), exact as f32);
+        }
+        let rows = [[0.1, 0.2, 0.3], [0.4, 0.5, 0.6], [0.7, 0.8, 0.9]];
+        // The rounded entries make the matrix slightly nonsingular.
+        assert_eq!(det3(rows), 4.163336342344336e-18);
+        assert_eq!(
+            det3([[2.0, 0.0, 0.0], [0.0, 3.0, 0.0], [0.0, 0.0, 0.5]]),
+            3.0
+        );
+    }
+
+    #[test]
+    fn merge() {
+        let values = [1e100, 0.1, 1.0, -1e100, 0.2, -0.3];
+        let (a, b) = values.split_at(3);
+        let mut kb: KahanBabuska<f64> = a.iter().sum();
+        kb.merge(&b.iter().sum());
+        assert_eq!(kb.total(), values.iter().sum::<KahanBabuska<f64>>().total());
+        let mut kbn: KahanBabuskaNeumaier<f64> = a.iter().sum();
+        kbn.merge(&b.iter().sum());
+        assert_eq!(kbn.total(), 1.0 + f64::EPSILON / 8.0);
+        let mut exact: ExactSum<f64> = a.iter().sum();
+        exact.merge(&b.iter().sum());
+        assert_eq!(exact, values.iter().sum());
+    }
+
     #[test]
     fn kahan_123() {
         let mut k = KahanBabuska::new();
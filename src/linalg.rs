@@ -0,0 +1,291 @@
+use crate::*;
+
+/// Memory layout of a dense matrix stored in a slice.
+///
+/// The distance between the starts of two consecutive rows (for [`Layout::RowMajor`]) or
+/// columns (for [`Layout::ColMajor`]) is called the *leading dimension* and is passed explicitly,
+/// so that submatrices of larger matrices can be used directly.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Layout {
+    /// Element $(i,j)$ is stored at index `i * ld + j`.
+    RowMajor,
+    /// Element $(i,j)$ is stored at index `i + j * ld`.
+    ColMajor,
+}
+
+impl Layout {
+    #[inline]
+    fn index(self, i: usize, j: usize, ld: usize) -> usize {
+        match self {
+            Layout::RowMajor => i * ld + j,
+            Layout::ColMajor => i + j * ld,
+        }
+    }
+
+    fn check(self, name: &str, rows: usize, cols: usize, len: usize, ld: usize) {
+        let (outer, inner) = match self {
+            Layout::RowMajor => (rows, cols),
+            Layout::ColMajor => (cols, rows),
+        };
+        assert!(
+            ld >= inner,
+            "leading dimension of `{name}` is too small: {ld} < {inner}"
+        );
+        if outer > 0 && inner > 0 {
+            let needed = (outer - 1) * ld + inner;
+            assert!(
+                len >= needed,
+                "slice `{name}` is too short: {len} < {needed}"
+            );
+        }
+    }
+}
+
+/// Compensated matrix-vector product $y=Ax$.
+///
+/// **Input:** an $m\times n$ matrix $A$ stored in `a` with the given `layout` and leading dimension `lda`, and a vector $x$ of length $n$.
+///
+/// **Output:** the vector $y$ of length $m$ is overwritten with $Ax$, each element being computed with the [`dot2`] algorithm.
+///
+/// # Panics
+///
+/// Panics if the dimensions of the slices are inconsistent with $m$, $n$ and `lda`.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::{gemv_compensated, Layout};
+/// let a = [1e16, 1.0, -1e16,
+///          0.1, 0.2, 0.3];
+/// let x = [1.0, 1.0, 1.0];
+/// let mut y = [0.0; 2];
+/// gemv_compensated(Layout::RowMajor, 2, 3, &a, 3, &x, &mut y);
+/// assert_eq!(y, [1.0, 0.6]);
+/// ```
+pub fn gemv_compensated<T: Float>(
+    layout: Layout,
+    m: usize,
+    n: usize,
+    a: &[T],
+    lda: usize,
+    x: &[T],
+    y: &mut [T],
+) {
+    layout.check("a", m, n, a.len(), lda);
+    assert_eq!(x.len(), n, "`x` must have length {n}");
+    assert_eq!(y.len(), m, "`y` must have length {m}");
+    if m == 0 || n == 0 {
+        // The slice `a` may be empty, whatever the leading dimension.
+        y.fill(T::zero());
+        return;
+    }
+
+    match layout {
+        Layout::RowMajor => {
+            for (i, yi) in y.iter_mut().enumerate() {
+                *yi = dot2(&a[i * lda..i * lda + n], x);
+            }
+        }
+        Layout::ColMajor => {
+            // Traverse the matrix by columns, keeping one accumulator for each row.
+            let mut acc = vec![DotAccumulator::new(); m];
+            for (j, &xj) in x.iter().enumerate() {
+                let col = &a[j * lda..j * lda + m];
+                for (acc, &aij) in acc.iter_mut().zip(col) {
+                    *acc += (aij, xj);
+                }
+            }
+            for (yi, acc) in y.iter_mut().zip(&acc) {
+                *yi = acc.total();
+            }
+        }
+    }
+}
+
+/// Side of the square blocks used by [`gemm_compensated`].
+const BLOCK: usize = 64;
+
+/// Compensated matrix-matrix product $C=AB$.
+///
+/// **Input:** an $m\times k$ matrix $A$ and a $k\times n$ matrix $B$, stored in `a` and `b` with leading dimensions `lda` and `ldb`.
+///
+/// **Output:** the $m\times n$ matrix $C$, stored in `c` with leading dimension `ldc`, is overwritten with $AB$,
+/// each element being computed with the [`dot2`] algorithm.
+/// Elements of `c` outside the $m\times n$ submatrix are left untouched.
+///
+/// All three matrices share the same `layout`.
+/// The computation is blocked, so that the working set fits in cache;
+/// the compensated accumulators of each block of $C$ are carried over the blocks of the inner dimension,
+/// hence the result does not depend on the blocking.
+///
+/// # Panics
+///
+/// Panics if the dimensions of the slices are inconsistent with $m$, $n$, $k$ and the leading dimensions.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::{gemm_compensated, Layout};
+/// let a = [1e16, 1.0, -1e16,
+///          0.1, 0.2, 0.3];
+/// let b = [1.0, 2.0,
+///          1.0, 2.0,
+///          1.0, 2.0];
+/// let mut c = [0.0; 4];
+/// gemm_compensated(Layout::RowMajor, 2, 2, 3, &a, 3, &b, 2, &mut c, 2);
+/// assert_eq!(c, [1.0, 2.0, 0.6, 1.2]);
+/// ```
+#[allow(clippy::too_many_arguments)]
+pub fn gemm_compensated<T: Float>(
+    layout: Layout,
+    m: usize,
+    n: usize,
+    k: usize,
+    a: &[T],
+    lda: usize,
+    b: &[T],
+    ldb: usize,
+    c: &mut [T],
+    ldc: usize,
+) {
+    layout.check("a", m, k, a.len(), lda);
+    layout.check("b", k, n, b.len(), ldb);
+    layout.check("c", m, n, c.len(), ldc);
+
+    let mut acc = vec![DotAccumulator::new(); BLOCK * BLOCK];
+    for i0 in (0..m).step_by(BLOCK) {
+        let i1 = (i0 + BLOCK).min(m);
+        for j0 in (0..n).step_by(BLOCK) {
+            let j1 = (j0 + BLOCK).min(n);
+            acc.fill(DotAccumulator::new());
+            for p0 in (0..k).step_by(BLOCK) {
+                let p1 = (p0 + BLOCK).min(k);
+                for i in i0..i1 {
+                    let row = &mut acc[(i - i0) * BLOCK..(i - i0 + 1) * BLOCK];
+                    for p in p0..p1 {
+                        let aip = a[layout.index(i, p, lda)];
+                        for j in j0..j1 {
+                            row[j - j0] += (aip, b[layout.index(p, j, ldb)]);
+                        }
+                    }
+                }
+            }
+            for i in i0..i1 {
+                for j in j0..j1 {
+                    c[layout.index(i, j, ldc)] = acc[(i - i0) * BLOCK + (j - j0)].total();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    fn transpose(layout: Layout, rows: usize, cols: usize, a: &[f64]) -> Vec<f64> {
+        let mut t = vec![0.0; rows * cols];
+        for i in 0..rows {
+            for j in 0..cols {
+                match layout {
+                    Layout::RowMajor => t[i + j * rows] = a[i * cols + j],
+                    Layout::ColMajor => t[i * cols + j] = a[i + j * rows],
+                }
+            }
+        }
+        t
+    }
+
+    #[test]
+    fn gemv_layouts() {
+        let a = [1e100, 1.0, -1e100, 1.0, 2.0, 3.0];
+        let x = [1.0, 1.0, 1.0];
+        let mut y = [0.0; 2];
+        gemv_compensated(Layout::RowMajor, 2, 3, &a, 3, &x, &mut y);
+        assert_eq!(y, [1.0, 6.0]);
+
+        let at = transpose(Layout::RowMajor, 2, 3, &a);
+        let mut z = [0.0; 2];
+        gemv_compensated(Layout::ColMajor, 2, 3, &at, 2, &x, &mut z);
+        assert_eq!(y, z);
+    }
+
+    #[test]
+    fn gemv_leading_dimension() {
+        // A 2x2 submatrix of a 3x3 matrix.
+        let nan = f64::NAN;
+        let a = [1.0, 2.0, nan, 3.0, 4.0, nan, nan, nan, nan];
+        let mut y = [0.0; 2];
+        gemv_compensated(Layout::RowMajor, 2, 2, &a, 3, &[1.0, -1.0], &mut y);
+        assert_eq!(y, [-1.0, -1.0]);
+        gemv_compensated(Layout::ColMajor, 2, 2, &a, 3, &[1.0, -1.0], &mut y);
+        assert_eq!(y, [-2.0, -2.0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn gemv_short_slice() {
+        let mut y = [0.0; 2];
+        gemv_compensated(
+            Layout::RowMajor,
+            2,
+            2,
+            &[1.0, 2.0, 3.0],
+            2,
+            &[1.0, 1.0],
+            &mut y,
+        );
+    }
+
+    #[test]
+    fn gemm_random() {
+        use rand::prelude::*;
+        use rand_distr::LogNormal;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+        let dist = LogNormal::new(0.0, 10.0).unwrap();
+        // Sizes not multiple of the block size, with mixed signs and magnitudes.
+        let (m, n, k) = (70, 67, 130);
+        let mut sample = |len: usize| -> Vec<f64> {
+            (0..len)
+                .map(|_| rng.sample(dist) * if rng.gen() { 1.0 } else { -1.0 })
+                .collect()
+        };
+        let a = sample(m * k);
+        let b = sample(k * n);
+
+        let mut c = vec![0.0; m * n];
+        gemm_compensated(Layout::RowMajor, m, n, k, &a, k, &b, n, &mut c, n);
+        for i in 0..m {
+            for j in 0..n {
+                let bj: Vec<f64> = (0..k).map(|p| b[p * n + j]).collect();
+                assert_eq!(c[i * n + j], dot2(&a[i * k..(i + 1) * k], &bj));
+            }
+        }
+
+        let at = transpose(Layout::RowMajor, m, k, &a);
+        let bt = transpose(Layout::RowMajor, k, n, &b);
+        let mut ct = vec![0.0; m * n];
+        gemm_compensated(Layout::ColMajor, m, n, k, &at, m, &bt, k, &mut ct, m);
+        assert_eq!(transpose(Layout::ColMajor, m, n, &ct), c);
+    }
+
+    #[test]
+    fn gemv_empty() {
+        for layout in [Layout::RowMajor, Layout::ColMajor] {
+            let mut y = [1.0; 2];
+            gemv_compensated(layout, 2, 0, &[], 5, &[], &mut y);
+            assert_eq!(y, [0.0; 2]);
+            let mut y: [f64; 0] = [];
+            gemv_compensated(layout, 0, 2, &[], 5, &[1.0, 2.0], &mut y);
+        }
+    }
+
+    #[test]
+    fn gemm_empty() {
+        let mut c = [1.0; 4];
+        gemm_compensated::<f64>(Layout::RowMajor, 2, 2, 0, &[], 0, &[], 2, &mut c, 2);
+        assert_eq!(c, [0.0; 4]);
+    }
+}
@@ -0,0 +1,232 @@
+use crate::*;
+
+/// This type is an accumulator for computing $\ln\sum_ie^{x_i}$ in a streaming fashion.
+///
+/// The generic parameter `T` should typically implement [`num_traits::float::Float`] and can usually be inferred.
+///
+/// The accumulator keeps the running maximum of the values, a reference $r$ close to it,
+/// and the sum $\sum_ie^{x_i-r}$ as a double-word, so that no term overflows or underflows unnecessarily
+/// and the sum does not accumulate rounding errors.
+/// When the maximum exceeds the reference by more than a fixed threshold, the sum is rescaled by $e^{r-r'}$
+/// as a double-word; since the sum shrinks by a large factor at each rescaling,
+/// the rounding errors of the rescaling factors do not accumulate even if the values arrive in increasing order.
+/// The result is affected only by the rounding of the exponentials.
+///
+/// Accumulators can be merged with [`LogSumExp::merge()`].
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::LogSumExp;
+/// let mut lse = LogSumExp::new();
+/// lse += 1000.0;
+/// lse += 1000.0;
+/// assert!((lse.total() - (1000.0 + 2f64.ln())).abs() < 1e-12);
+/// // The naive computation overflows.
+/// assert_eq!((1000f64.exp() + 1000f64.exp()).ln(), f64::INFINITY);
+/// ```
+///
+/// [`LogSumExp`] also implements the [`std::iter::Sum`](#impl-Sum<V>-for-LogSumExp<T>) trait,
+/// hence an iterator of log-likelihoods can be summed.
+///
+/// ```
+/// # use compensated_summation::LogSumExp;
+/// let log_p = [-1e4, 0.0, -1e4, -2f64.ln()];
+/// let lse: LogSumExp<f64> = log_p.iter().sum();
+/// assert_eq!(lse.total(), 1.5f64.ln());
+/// assert_eq!(LogSumExp::<f64>::new().total(), f64::NEG_INFINITY);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogSumExp<T> {
+    /// Running maximum.
+    max: T,
+    /// Reference, not greater than the maximum by more than `THRESHOLD`.
+    shift: T,
+    /// Sum of `exp(x - shift)`, as a double-word.
+    sum: (T, T),
+}
+
+// Maximum difference between the running maximum and the reference, so that the terms are at most `e^16`.
+const THRESHOLD: f64 = 16.0;
+
+impl<T: Float> LogSumExp<T> {
+    /// Create a new empty accumulator.
+    pub fn new() -> Self {
+        Self {
+            max: T::neg_infinity(),
+            shift: T::neg_infinity(),
+            sum: (T::zero(), T::zero()),
+        }
+    }
+
+    /// Get the maximum of the accumulated values, which is $-\infty$ if none has been added.
+    pub fn max(&self) -> T {
+        self.max
+    }
+
+    /// Get the estimated $\ln\sum_ie^{x_i}$, which is $-\infty$ if no value has been added.
+    pub fn total(&self) -> T {
+        if !self.max.is_finite() {
+            return self.max;
+        }
+        let (h, l) = self.sum;
+        // The sum is at least one, and `ln(h + l) = ln(h) + l/h` to first order.
+        self.shift + (h.ln() + l / h)
+    }
+
+    /// Merge another accumulator into this one, as if all its values had been added to this one.
+    pub fn merge(&mut self, other: &Self) {
+        if other.max.is_nan() || other.max == T::neg_infinity() {
+            if other.max.is_nan() {
+                self.max = other.max;
+            }
+            return;
+        }
+        if self.max.is_nan() || self.max == T::infinity() {
+            return;
+        }
+        if other.max == T::infinity() {
+            self.max = other.max;
+            return;
+        }
+        let (mut a, b) = if other.shift > self.shift {
+            (other.clone(), &*self)
+        } else {
+            (self.clone(), other)
+        };
+        let (h, l) = mul_dw(b.sum, (b.shift - a.shift).exp());
+        a.sum = add_dw(add_dw(a.sum, h), l);
+        a.max = a.max.max(b.max);
+        *self = a;
+    }
+}
+
+impl<T: Float> Default for LogSumExp<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> Accumulator for LogSumExp<T> {
+    type Output = T;
+    fn total(&self) -> T {
+        self.total()
+    }
+    fn merge(&mut self, other: &Self) {
+        self.merge(other)
+    }
+}
+
+impl<T: Float> AddAssign<T> for LogSumExp<T> {
+    fn add_assign(&mut self, rhs: T) {
+        if self.max.is_nan() {
+            return;
+        }
+        if rhs.is_nan() || rhs > self.max {
+            self.max = rhs;
+        }
+        if !self.max.is_finite() || rhs == T::neg_infinity() {
+            return;
+        }
+        if rhs > self.shift + T::from(THRESHOLD).unwrap() {
+            // The previous sum is rescaled to the new reference, whose own term is one.
+            self.sum = mul_dw(self.sum, (self.shift - rhs).exp());
+            self.shift = rhs;
+        }
+        self.sum = add_dw(self.sum, (rhs - self.shift).exp());
+    }
+}
+
+impl<T: Float> AddAssign<&T> for LogSumExp<T> {
+    fn add_assign(&mut self, rhs: &T) {
+        *self += *rhs;
+    }
+}
+
+impl<T: Float, V> Add<V> for LogSumExp<T>
+where
+    Self: AddAssign<V>,
+{
+    type Output = Self;
+    fn add(mut self, rhs: V) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl<T: Float, V> Sum<V> for LogSumExp<T>
+where
+    Self: AddAssign<V>,
+{
+    fn sum<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = V>,
+    {
+        let mut lse = LogSumExp::new();
+        for x in iter {
+            lse += x;
+        }
+        lse
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn logsumexp_vs_f64() {
+        use rand::prelude::*;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        // Values in increasing order cause many rescalings.
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+        for sorted in [false, true] {
+            let mut x: Vec<f32> = (0..100_000).map(|_| rng.gen_range(-20.0..20.0)).collect();
+            if sorted {
+                x.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            }
+            let lse: LogSumExp<f32> = x.iter().sum();
+            let reference = x.iter().map(|&x| (x as f64 - 20.0).exp()).sum::<f64>().ln() + 20.0;
+            let m = x.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+            assert_eq!(lse.max(), m);
+            let naive = m + x.iter().map(|&x| (x - m).exp()).sum::<f32>().ln();
+            // The result is rounded, and a relative error in the sum is an absolute error in its logarithm.
+            let tol = f32::EPSILON as f64 * (reference.abs() + 4.0);
+            assert!((lse.total() as f64 - reference).abs() <= tol);
+            // Summing in increasing order is accurate even without compensation.
+            if !sorted {
+                assert!((naive as f64 - reference).abs() > tol);
+            }
+        }
+    }
+
+    #[test]
+    fn logsumexp_merge() {
+        let x: Vec<f64> = (0..1000).map(|i| (i as f64 * 0.37).sin() * 800.0).collect();
+        let all: LogSumExp<f64> = x.iter().sum();
+        for split in [0, 1, 500, 999, 1000] {
+            let mut a: LogSumExp<f64> = x[..split].iter().sum();
+            let b: LogSumExp<f64> = x[split..].iter().sum();
+            a.merge(&b);
+            assert!((a.total() - all.total()).abs() <= 4.0 * f64::EPSILON * all.total());
+            assert_eq!(a.max(), all.max());
+        }
+    }
+
+    #[test]
+    fn logsumexp_special() {
+        let lse = LogSumExp::new() + f64::NEG_INFINITY + 0.0 + f64::NEG_INFINITY;
+        assert_eq!(lse.total(), 0.0);
+        let lse = LogSumExp::new() + 1.0 + f64::INFINITY + f64::INFINITY + 2.0;
+        assert_eq!(lse.total(), f64::INFINITY);
+        let lse = LogSumExp::new() + 1.0 + f64::NAN + f64::INFINITY;
+        assert!(lse.total().is_nan());
+        let mut a = LogSumExp::new() + 1.0;
+        a.merge(&LogSumExp::new());
+        assert_eq!(a.total(), 1.0);
+        let mut b = LogSumExp::new();
+        b.merge(&a);
+        assert_eq!(b, a);
+    }
+}
@@ -0,0 +1,524 @@
+use crate::*;
+
+/// Euclidean norm, computed without overflow or underflow and with compensation.
+///
+/// **Input:** a slice $x$ of floating-point numbers.
+///
+/// **Output:** the Euclidean norm $\|x\|_2=\sqrt{\sum_i x_i^2}$.
+///
+/// This is a shorthand for summing the slice with a [`Norm2Accumulator`], see its documentation for the details.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::norm2;
+/// assert_eq!(norm2(&[3.0, 4.0]), 5.0);
+/// assert_eq!(norm2(&[3e300, 4e300]), 5e300);
+/// assert_eq!(norm2(&[3e-300, 4e-300]), 5e-300);
+/// ```
+pub fn norm2<T: Float>(x: &[T]) -> T {
+    x.iter().sum::<Norm2Accumulator<T>>().total()
+}
+
+/// This type is an accumulator for computing the Euclidean norm of a sequence of floating-point numbers.
+///
+/// The generic parameter `T` should typically implement [`num_traits::float::Float`] and can usually be inferred.
+///
+/// The sum of squares is represented as $(\mathrm{sum}+\mathrm{comp})\cdot4^\mathrm{exp}$, where the
+/// scale $2^\mathrm{exp}$ follows the largest magnitude seen so far (like the scaling of LAPACK's `nrm2`,
+/// but with powers of two, so that scaling is exact). Hence no overflow or underflow occurs for any finite input.
+/// Each scaled square is computed with [`two_prod`] and accumulated with [`two_sum`],
+/// and the final square root is corrected with one Newton step in double-word arithmetic.
+///
+/// The total is infinite if any input is infinite, otherwise NaN if any input is NaN.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::Norm2Accumulator;
+/// let mut norm = Norm2Accumulator::new();
+/// norm += 1e-300;
+/// norm += 3.0;
+/// norm += 4.0;
+/// assert_eq!(norm.total(), 5.0);
+/// ```
+///
+/// [`Norm2Accumulator`] also implements the [`std::iter::Sum`](#impl-Sum<V>-for-Norm2Accumulator<T>) trait.
+///
+/// ```
+/// # use compensated_summation::Norm2Accumulator;
+/// let iter = [1e300; 4].iter();
+/// assert_eq!(iter.sum::<Norm2Accumulator<f64>>().total(), 2e300);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Norm2Accumulator<T> {
+    /// Accumulated sum of the scaled squares.
+    sum: T,
+    /// Compensation of the error.
+    comp: T,
+    /// Binary exponent of the scale.
+    exp: i64,
+    /// Whether an infinity has been seen.
+    inf: bool,
+    /// Whether a NaN has been seen.
+    nan: bool,
+}
+
+impl<T: Float> Norm2Accumulator<T> {
+    /// Create a new empty accumulator.
+    pub fn new() -> Self {
+        Self {
+            sum: T::zero(),
+            comp: T::zero(),
+            exp: 0,
+            inf: false,
+            nan: false,
+        }
+    }
+
+    /// Get the Euclidean norm.
+    pub fn total(&self) -> T {
+        if self.inf {
+            T::infinity()
+        } else if self.nan {
+            T::nan()
+        } else {
+            ldexp(sqrt_dw(self.sum, self.comp), self.exp)
+        }
+    }
+
+    /// Get the sum of squares.
+    ///
+    /// This overflows or underflows if the sum of squares is not representable, contrary to [`Norm2Accumulator::total()`].
+    pub fn sum_of_squares(&self) -> T {
+        let norm = self.total();
+        if norm.is_finite() {
+            ldexp(self.sum + self.comp, 2 * self.exp)
+        } else {
+            norm
+        }
+    }
+
+    /// Merge another accumulator into this one, as if all its values had been added to this one.
+    pub fn merge(&mut self, other: &Self) {
+        self.inf |= other.inf;
+        self.nan |= other.nan;
+        if other.sum == T::zero() {
+            return;
+        }
+        if self.sum == T::zero() {
+            self.sum = other.sum;
+            self.comp = other.comp;
+            self.exp = other.exp;
+            return;
+        }
+        // Bring both sums to the larger scale, which is exact except for negligible terms.
+        let exp = self.exp.max(other.exp);
+        let (k, l) = (2 * (self.exp - exp), 2 * (other.exp - exp));
+        let (s, δs) = two_sum(ldexp(self.sum, k), ldexp(other.sum, l));
+        self.sum = s;
+        self.comp = ldexp(self.comp, k) + (ldexp(other.comp, l) + δs);
+        self.exp = exp;
+    }
+}
+
+impl<T: Float> Default for Norm2Accumulator<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> Accumulator for Norm2Accumulator<T> {
+    type Output = T;
+    fn total(&self) -> T {
+        self.total()
+    }
+    fn merge(&mut self, other: &Self) {
+        self.merge(other)
+    }
+}
+
+impl<T: Float> AddAssign<T> for Norm2Accumulator<T> {
+    fn add_assign(&mut self, rhs: T) {
+        if rhs.is_nan() {
+            self.nan = true;
+            return;
+        }
+        if rhs.is_infinite() {
+            self.inf = true;
+            return;
+        }
+        if rhs == T::zero() {
+            return;
+        }
+        let (_, e) = frexp(rhs);
+        if self.sum == T::zero() {
+            self.exp = e;
+        } else if e > self.exp {
+            // Rescaling by a power of four is exact, except for the underflow of negligible terms.
+            let k = 2 * (self.exp - e);
+            self.sum = ldexp(self.sum, k);
+            self.comp = ldexp(self.comp, k);
+            self.exp = e;
+        }
+        let y = ldexp(rhs, -self.exp);
+        let (p, δp) = two_prod(y, y);
+        let (s, δs) = two_sum(self.sum, p);
+        self.sum = s;
+        self.comp = self.comp + (δp + δs);
+    }
+}
+
+impl<T: Float> AddAssign<&T> for Norm2Accumulator<T> {
+    fn add_assign(&mut self, rhs: &T) {
+        *self += *rhs;
+    }
+}
+
+impl<T: Float, V> Add<V> for Norm2Accumulator<T>
+where
+    Self: AddAssign<V>,
+{
+    type Output = Self;
+    fn add(mut self, rhs: V) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl<T: Float, V> Sum<V> for Norm2Accumulator<T>
+where
+    Self: AddAssign<V>,
+{
+    fn sum<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = V>,
+    {
+        let mut norm = Norm2Accumulator::new();
+        for x in iter {
+            norm += x;
+        }
+        norm
+    }
+}
+
+/// This type is an accumulator for computing a compensated sum of squares.
+///
+/// The generic parameter `T` should typically implement [`num_traits::float::Float`] and can usually be inferred.
+///
+/// Each square $x^2$ is computed exactly with [`two_prod`] and its floating-point value is added with [`two_sum`]:
+/// both the error of the product and the error of the addition are accumulated in the compensation term.
+/// Contrary to [`Norm2Accumulator`], no scaling is performed, hence this is faster but the squares may overflow or underflow.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::SumOfSquares;
+/// let mut sum = SumOfSquares::new();
+/// sum += 1.0;
+/// sum += -2.0;
+/// sum += 3.0;
+/// assert_eq!(sum.count, 3);
+/// assert_eq!(sum.total(), 14.0);
+/// assert_eq!(sum.rms(), (14.0f64 / 3.0).sqrt());
+/// ```
+///
+/// Accumulators computed separately (for example, on different blocks of a signal) can be merged.
+///
+/// ```
+/// # use compensated_summation::SumOfSquares;
+/// let mut a: SumOfSquares<f64> = [0.1, 0.2].iter().sum();
+/// let b: SumOfSquares<f64> = [0.3].iter().sum();
+/// a.merge(&b);
+/// assert_eq!(a, [0.1, 0.2, 0.3].iter().sum());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SumOfSquares<T> {
+    /// Accumulated sum of the squares.
+    pub sum: T,
+    /// Compensation of the error.
+    pub comp: T,
+    /// Number of accumulated values.
+    pub count: usize,
+}
+
+impl<T: Float> SumOfSquares<T> {
+    /// Create a new empty accumulator.
+    pub fn new() -> Self {
+        Self {
+            sum: T::zero(),
+            comp: T::zero(),
+            count: 0,
+        }
+    }
+
+    /// Get the estimated sum of squares.
+    pub fn total(&self) -> T {
+        self.sum + self.comp
+    }
+
+    /// Get the root mean square, that is, the square root of the mean of the squares.
+    ///
+    /// This is NaN if the accumulator is empty.
+    pub fn rms(&self) -> T {
+        (self.total() / T::from(self.count).unwrap()).sqrt()
+    }
+
+    /// Merge another accumulator into this one, as if all its values had been added to this one.
+    pub fn merge(&mut self, other: &Self) {
+        let (s, c) = two_sum(self.sum, other.sum);
+        self.sum = s;
+        self.comp = self.comp + (other.comp + c);
+        self.count += other.count;
+    }
+}
+
+impl<T: Float> Default for SumOfSquares<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> Accumulator for SumOfSquares<T> {
+    type Output = T;
+    fn total(&self) -> T {
+        self.total()
+    }
+    fn merge(&mut self, other: &Self) {
+        self.merge(other)
+    }
+}
+
+impl<T: Float> AddAssign<T> for SumOfSquares<T> {
+    fn add_assign(&mut self, rhs: T) {
+        let (p, δp) = two_prod(rhs, rhs);
+        let (s, δs) = two_sum(self.sum, p);
+        self.sum = s;
+        self.comp = self.comp + (δp + δs);
+        self.count += 1;
+    }
+}
+
+impl<T: Float> AddAssign<&T> for SumOfSquares<T> {
+    fn add_assign(&mut self, rhs: &T) {
+        *self += *rhs;
+    }
+}
+
+impl<T: Float, V> Add<V> for SumOfSquares<T>
+where
+    Self: AddAssign<V>,
+{
+    type Output = Self;
+    fn add(mut self, rhs: V) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl<T: Float, V> Sum<V> for SumOfSquares<T>
+where
+    Self: AddAssign<V>,
+{
+    fn sum<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = V>,
+    {
+        let mut sum = SumOfSquares::new();
+        for x in iter {
+            sum += x;
+        }
+        sum
+    }
+}
+
+/// Euclidean distance $\sqrt{x^2+y^2}$ of double-word inputs.
+///
+/// **Input:** two double-words $x=x_h+x_l$ and $y=y_h+y_l$, given as tuples `(hi, lo)`
+/// (such as those returned by [`two_sum`] or [`comp_horner_dw`]).
+/// The double-words need not be normalized: they are renormalized with [`two_sum`] first.
+///
+/// **Output:** $\sqrt{x^2+y^2}$, rounded to working precision, with an error of little more than half an ulp.
+///
+/// The inputs are scaled by a power of two, so that no overflow or underflow occurs;
+/// the squares are computed in double-word arithmetic with [`two_prod`] and summed with [`two_sum`],
+/// and the square root is corrected with one Newton step.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::hypot_dw;
+/// assert_eq!(hypot_dw((3e300, 0.0), (4e300, 0.0)), 5e300);
+/// // 0.3 + 0.1 is not 0.4 in floating point, but the error is carried by the double-word.
+/// let x = compensated_summation::two_sum(0.3, 0.1);
+/// assert_eq!(hypot_dw(x, (0.0, 0.0)), 0.3 + 0.1);
+/// ```
+pub fn hypot_dw<T: Float>((xh, xl): (T, T), (yh, yl): (T, T)) -> T {
+    if [xh, xl, yh, yl].iter().any(|v| v.is_infinite()) {
+        return T::infinity();
+    }
+    if [xh, xl, yh, yl].iter().any(|v| v.is_nan()) {
+        return T::nan();
+    }
+    let (xh, xl) = two_sum(xh, xl);
+    let (yh, yl) = two_sum(yh, yl);
+    let m = xh.abs().max(yh.abs());
+    if m == T::zero() {
+        return T::zero();
+    }
+    let (_, e) = frexp(m);
+    let [xh, xl, yh, yl] = [xh, xl, yh, yl].map(|v| ldexp(v, -e));
+    let (px, δx) = square_dw(xh, xl);
+    let (py, δy) = square_dw(yh, yl);
+    let (s, δs) = two_sum(px, py);
+    ldexp(sqrt_dw(s, δs + (δx + δy)), e)
+}
+
+// Square of the double-word `h + l`, as a double-word (up to rounding of the low part).
+fn square_dw<T: Float>(h: T, l: T) -> (T, T) {
+    let (p, δp) = two_prod(h, h);
+    let two_h = h + h;
+    (p, two_h.mul_add(l, l.mul_add(l, δp)))
+}
+
+// Square root of the double-word `h + l`, with one Newton step.
+fn sqrt_dw<T: Float>(h: T, l: T) -> T {
+    let r = h.sqrt();
+    if r == T::zero() || !r.is_finite() {
+        return r;
+    }
+    let (p, δp) = two_prod(r, r);
+    let residual = ((h - p) - δp) + l;
+    r + residual / (r + r)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn norm2_vs_f64() {
+        use rand::prelude::*;
+        use rand_distr::LogNormal;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        // The squares of `f32` numbers are exact in `f64`, hence the reference is correctly rounded.
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+        let dist = LogNormal::new(0.0, 5.0).unwrap();
+        let mut exact = 0;
+        for _ in 0..1000 {
+            let values: Vec<f32> = (0..100).map(|_| rng.sample(dist)).collect();
+            let squares = values.iter().map(|&x| x as f64 * x as f64);
+            let reference = squares.sum::<ExactSum<f64>>().total().sqrt() as f32;
+            let norm = norm2(&values);
+            assert!((norm - reference).abs() <= reference * f32::EPSILON);
+            if norm == reference {
+                exact += 1;
+            }
+        }
+        assert!(exact > 990);
+    }
+
+    #[test]
+    fn norm2_extreme() {
+        assert_eq!(norm2(&[f64::MAX, f64::MAX]), f64::INFINITY);
+        assert_eq!(norm2(&[f64::MAX, 0.0]), f64::MAX);
+        let n = norm2(&[f64::MAX / 2.0, f64::MAX / 2.0]);
+        assert!((n - f64::MAX / 2.0 * 2f64.sqrt()).abs() <= n * f64::EPSILON);
+        let tiny = f64::MIN_POSITIVE * f64::EPSILON;
+        assert_eq!(norm2(&[tiny; 4]), 2.0 * tiny);
+        assert_eq!(norm2(&[1e-300, 1e300, 1e-300]), 1e300);
+        assert_eq!(norm2(&[-3.0, 0.0, -4.0]), 5.0);
+        assert_eq!(norm2::<f64>(&[]), 0.0);
+    }
+
+    #[test]
+    fn norm2_special() {
+        assert_eq!(norm2(&[1.0, f64::NAN, f64::INFINITY]), f64::INFINITY);
+        assert_eq!(norm2(&[1.0, f64::NEG_INFINITY]), f64::INFINITY);
+        assert!(norm2(&[1.0, f64::NAN]).is_nan());
+    }
+
+    #[test]
+    fn norm2_sum_of_squares() {
+        let norm: Norm2Accumulator<f64> = [1e200, 1e-200, 3.0].iter().sum();
+        assert_eq!(norm.sum_of_squares(), f64::INFINITY);
+        let norm: Norm2Accumulator<f64> = [1e-200, 3.0, 4.0].iter().sum();
+        assert_eq!(norm.sum_of_squares(), 25.0);
+    }
+
+    #[test]
+    fn sum_of_squares_vs_exact() {
+        use rand::prelude::*;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+        for _ in 0..100 {
+            let values: Vec<f64> = (0..1000).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            let mut exact = ExactSum::new();
+            for &x in &values {
+                let (p, e) = two_prod(x, x);
+                exact += p;
+                exact += e;
+            }
+            let exact = exact.total();
+            let sum: SumOfSquares<f64> = values.iter().sum();
+            assert_eq!(sum.count, 1000);
+            assert!((sum.total() - exact).abs() <= exact * f64::EPSILON / 2.0);
+        }
+    }
+
+    #[test]
+    fn sum_of_squares_product_error() {
+        // (1 + ε)^2 = 1 + 2ε + ε^2, where ε^2 is lost by the floating-point product.
+        let e = f64::EPSILON;
+        let sum: SumOfSquares<f64> = [1.0 + e, 1.0].iter().sum();
+        assert_eq!(sum.sum, 2.0 + 2.0 * e);
+        assert_eq!(sum.comp, e * e);
+        assert_eq!(
+            KahanBabuskaNeumaier::new() + (1.0 + e) * (1.0 + e) + 1.0,
+            KahanBabuskaNeumaier {
+                sum: 2.0 + 2.0 * e,
+                comp: 0.0
+            }
+        );
+    }
+
+    #[test]
+    fn sum_of_squares_merge() {
+        let values: Vec<f64> = (1..=100).map(|i| 1.0 / i as f64).collect();
+        let mut merged = SumOfSquares::new();
+        for chunk in values.chunks(7) {
+            merged.merge(&chunk.iter().sum());
+        }
+        let sum: SumOfSquares<f64> = values.iter().sum();
+        assert_eq!(merged.count, sum.count);
+        assert!((merged.total() - sum.total()).abs() <= sum.total() * f64::EPSILON);
+        assert!(SumOfSquares::<f64>::new().rms().is_nan());
+    }
+
+    #[test]
+    fn hypot_dw_vs_f64() {
+        use rand::prelude::*;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+        for _ in 0..1000 {
+            let [a, b, c, d]: [f32; 4] = std::array::from_fn(|_| rng.gen_range(-10.0..10.0));
+            let x = two_sum(a, b);
+            let y = two_sum(c, d);
+            let reference = (a as f64 + b as f64).hypot(c as f64 + d as f64) as f32;
+            let h = hypot_dw(x, y);
+            assert!((h - reference).abs() <= reference * f32::EPSILON / 2.0);
+        }
+        assert_eq!(hypot_dw((0.0, 0.0), (-0.0, 0.0)), 0.0);
+        // Unnormalized double-words.
+        assert_eq!(hypot_dw((1.0, 1.0), (0.0, 0.0)), 2.0);
+        assert_eq!(hypot_dw((0.0, 3.0), (4.0, 0.0)), 5.0);
+        assert_eq!(hypot_dw((1e-300, 3e300), (0.0, 4e300)), 5e300);
+        assert_eq!(
+            hypot_dw((f64::NAN, 0.0), (f64::INFINITY, 0.0)),
+            f64::INFINITY
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use crate::*;
+
+/// This type is a state vector with compensated updates, for integrating ordinary differential equations.
+///
+/// The generic parameter `T` should typically implement [`num_traits::float::Float`] and can usually be inferred,
+/// and `N` is the dimension of the state.
+///
+/// Each component is a [`KahanBabuskaNeumaier`] accumulator: the increment $hk_i$ is split exactly with [`two_prod`]
+/// and both parts are added to it.
+/// Unlike a plain [`KahanBabuskaNeumaier`] sum, the accumulator is renormalized with [`fast_two_sum`] after each update,
+/// so that the compensation stays below half an ulp of the component: otherwise the compensation is itself summed
+/// without compensation, and over millions of steps in single precision its own rounding errors become visible.
+/// With small steps, the round-off error of a plain state vector grows linearly with the number of steps,
+/// and eventually dominates the truncation error of the method; with compensation, it does not.
+///
+/// The reference steppers [`CompensatedState::euler_step()`] and [`CompensatedState::rk4_step()`] are provided;
+/// other explicit and symplectic integrators can be built on [`CompensatedState::axpy()`].
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::CompensatedState;
+/// // Exponential decay y' = -y, integrated from 0 to 1.
+/// let mut y = CompensatedState::new([1.0]);
+/// let h = 1e-4;
+/// for i in 0..10_000 {
+///     y.rk4_step(i as f64 * h, h, |_, y| [-y[0]]);
+/// }
+/// let y1: f64 = y.total()[0];
+/// assert!((y1 - (-1f64).exp()).abs() < 1e-15);
+/// ```
+///
+/// A symplectic (semi-implicit) Euler step for a harmonic oscillator.
+///
+/// ```
+/// # use compensated_summation::CompensatedState;
+/// let mut q = CompensatedState::new([1.0]);
+/// let mut p = CompensatedState::new([0.0]);
+/// let h: f64 = 1e-3;
+/// for _ in 0..1000 {
+///     p.axpy(-h, &q.total());
+///     q.axpy(h, &p.total());
+/// }
+/// let [q1] = q.total();
+/// let [p1] = p.total();
+/// assert!((q1 * q1 + p1 * p1 - 1.0).abs() < 1e-3);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompensatedState<T, const N: usize> {
+    /// Accumulator of each component.
+    acc: [KahanBabuskaNeumaier<T>; N],
+}
+
+impl<T: Float, const N: usize> CompensatedState<T, N> {
+    /// Create a new state with the given components.
+    pub fn new(x: [T; N]) -> Self {
+        Self {
+            acc: x.map(|x| KahanBabuskaNeumaier {
+                sum: x,
+                comp: T::zero(),
+            }),
+        }
+    }
+
+    /// Get the estimated components.
+    pub fn total(&self) -> [T; N] {
+        self.acc.each_ref().map(KahanBabuskaNeumaier::total)
+    }
+
+    /// Add $hk$ to the state.
+    pub fn axpy(&mut self, h: T, k: &[T; N]) {
+        for (acc, &k) in self.acc.iter_mut().zip(k) {
+            let (p, δp) = two_prod(h, k);
+            *acc += p;
+            *acc += δp;
+            (acc.sum, acc.comp) = fast_two_sum(acc.sum, acc.comp);
+        }
+    }
+
+    /// Advance the state $y$ by one step of the explicit Euler method for $y'=f(t,y)$,
+    /// that is $y\leftarrow y+hf(t,y)$.
+    pub fn euler_step<F>(&mut self, t: T, h: T, mut f: F)
+    where
+        F: FnMut(T, &[T; N]) -> [T; N],
+    {
+        let k = f(t, &self.total());
+        self.axpy(h, &k);
+    }
+
+    /// Advance the state $y$ by one step of the classical Runge-Kutta method of order 4 for $y'=f(t,y)$.
+    ///
+    /// The stages are evaluated at the rounded state, and only the final update is compensated.
+    pub fn rk4_step<F>(&mut self, t: T, h: T, mut f: F)
+    where
+        F: FnMut(T, &[T; N]) -> [T; N],
+    {
+        let two = T::one() + T::one();
+        let six = two + two + two;
+        let half = h / two;
+        let y = self.total();
+        let stage = |k: &[T; N], h: T| -> [T; N] { std::array::from_fn(|i| k[i].mul_add(h, y[i])) };
+        let k1 = f(t, &y);
+        let k2 = f(t + half, &stage(&k1, half));
+        let k3 = f(t + half, &stage(&k2, half));
+        let k4 = f(t + h, &stage(&k3, h));
+        let k: [T; N] = std::array::from_fn(|i| (k1[i] + two * (k2[i] + k3[i]) + k4[i]) / six);
+        self.axpy(h, &k);
+    }
+}
+
+impl<T: Float, const N: usize> Default for CompensatedState<T, N> {
+    fn default() -> Self {
+        Self::new([T::zero(); N])
+    }
+}
+
+impl<T: Float, const N: usize> AddAssign<[T; N]> for CompensatedState<T, N> {
+    fn add_assign(&mut self, rhs: [T; N]) {
+        self.axpy(T::one(), &rhs);
+    }
+}
+
+impl<T: Float, const N: usize> AddAssign<&[T; N]> for CompensatedState<T, N> {
+    fn add_assign(&mut self, rhs: &[T; N]) {
+        self.axpy(T::one(), rhs);
+    }
+}
+
+impl<T: Float, const N: usize> AddAssign<(T, [T; N])> for CompensatedState<T, N> {
+    fn add_assign(&mut self, (h, k): (T, [T; N])) {
+        self.axpy(h, &k);
+    }
+}
+
+impl<T: Float, const N: usize> AddAssign<(T, &[T; N])> for CompensatedState<T, N> {
+    fn add_assign(&mut self, (h, k): (T, &[T; N])) {
+        self.axpy(h, k);
+    }
+}
+
+impl<T: Float, const N: usize, V> Add<V> for CompensatedState<T, N>
+where
+    Self: AddAssign<V>,
+{
+    type Output = Self;
+    fn add(mut self, rhs: V) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn euler_no_drift() {
+        // With a constant derivative, the Euler method is exact up to round-off.
+        let mut y = CompensatedState::new([1.0f32, -2.0]);
+        let mut naive = [1.0f32, -2.0];
+        let h = 1e-3;
+        let n = 1_000_000;
+        for i in 0..n {
+            y.euler_step(i as f32 * h, h, |_, _| [0.1, 3.0]);
+            naive[0] += h * 0.1;
+            naive[1] += h * 3.0;
+        }
+        let exact = [
+            (1.0 + n as f64 * (h as f64 * 0.1f32 as f64)) as f32,
+            (-2.0 + n as f64 * (h as f64 * 3.0)) as f32,
+        ];
+        assert_eq!(y.total(), exact);
+        assert!((naive[0] - exact[0]).abs() > 100.0 * f32::EPSILON * exact[0]);
+    }
+
+    #[test]
+    fn rk4_vs_f64() {
+        // The same integration in `f64` is a reference for the round-off error in `f32`.
+        let f32_rhs = |_: f32, y: &[f32; 2]| [y[1], -y[0]];
+        let f64_rhs = |_: f64, y: &[f64; 2]| [y[1], -y[0]];
+        let h = 2f32.powi(-10);
+        let mut y = CompensatedState::new([1.0f32, 0.0]);
+        let mut naive = [1.0f32, 0.0];
+        let naive_rk4 = |t: f32, y: [f32; 2]| {
+            let stage = |k: [f32; 2], h: f32| [k[0].mul_add(h, y[0]), k[1].mul_add(h, y[1])];
+            let k1 = f32_rhs(t, &y);
+            let k2 = f32_rhs(t + h / 2.0, &stage(k1, h / 2.0));
+            let k3 = f32_rhs(t + h / 2.0, &stage(k2, h / 2.0));
+            let k4 = f32_rhs(t + h, &stage(k3, h));
+            let k = |i: usize| (k1[i] + 2.0 * (k2[i] + k3[i]) + k4[i]) / 6.0;
+            [y[0] + h * k(0), y[1] + h * k(1)]
+        };
+        let mut reference = CompensatedState::new([1.0f64, 0.0]);
+        for i in 0..10_000 {
+            let t = i as f32 * h;
+            y.rk4_step(t, h, f32_rhs);
+            naive = naive_rk4(t, naive);
+            reference.rk4_step(t as f64, h as f64, f64_rhs);
+        }
+        let reference = reference.total();
+        let error = |y: [f32; 2]| {
+            (y[0] as f64 - reference[0])
+                .abs()
+                .max((y[1] as f64 - reference[1]).abs())
+        };
+        assert!(error(y.total()) < 1e-5);
+        assert!(error(naive) > 10.0 * error(y.total()));
+    }
+
+    #[test]
+    fn state_ops() {
+        let y = CompensatedState::new([1e100, 1.0]) + [1.0, 1.0] + (2.0, [-5e99, 0.5]);
+        assert_eq!(y.total(), [1.0, 3.0]);
+        let mut z = CompensatedState::<f64, 3>::default();
+        z += &[1.0, 2.0, 3.0];
+        z += (0.5, &[2.0, 2.0, 2.0]);
+        assert_eq!(z.total(), [2.0, 3.0, 4.0]);
+    }
+}
@@ -0,0 +1,293 @@
+use crate::*;
+
+/// This type is a phase accumulator, for angles advancing by many small increments and wrapping around.
+///
+/// The generic parameter `T` should typically implement [`num_traits::float::Float`] and can usually be inferred.
+///
+/// The phase and the period are kept as double-words, and the phase is reduced to $\[0,P)$ after each increment
+/// by subtracting an integer multiple of the period exactly, so that the compensation term is carried across
+/// the wraps instead of being lost like with `(phase + dx) % period`.
+/// The period $2\pi$ of [`PhaseAccumulator::radians()`] is itself represented as a double-word,
+/// hence the phase does not drift after many turns either.
+///
+/// The number of complete turns is counted by [`PhaseAccumulator::wraps()`].
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::PhaseAccumulator;
+/// let mut phase = PhaseAccumulator::turns();
+/// for _ in 0..1_000_000 {
+///     phase += 0.1;
+/// }
+/// // Ten steps of `0.1` are exactly `1 + 2^-54`.
+/// assert_eq!(phase.wraps(), 100_000);
+/// assert_eq!(phase.phase(), 1e5 * 2f64.powi(-54));
+/// ```
+///
+/// Since `PI` is slightly smaller than $\pi$, two half turns of `PI` are just short of a full turn.
+///
+/// ```
+/// # use compensated_summation::PhaseAccumulator;
+/// use std::f64::consts::{PI, TAU};
+/// let mut phase = PhaseAccumulator::radians();
+/// phase += PI;
+/// phase += PI;
+/// assert_eq!(phase.wraps(), 0);
+/// assert_eq!(phase.phase(), TAU);
+/// assert!(phase.centered_phase() < 0.0);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhaseAccumulator<T> {
+    /// Current phase, as a double-word in $\[0,P)$.
+    phase: (T, T),
+    /// Period, as a double-word.
+    period: (T, T),
+    /// Number of complete turns.
+    wraps: i64,
+}
+
+impl<T: Float> PhaseAccumulator<T> {
+    /// Create a new phase accumulator at phase zero with the given period.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `period` is not positive and finite.
+    pub fn new(period: T) -> Self {
+        assert!(
+            period > T::zero() && period.is_finite(),
+            "the period must be positive and finite"
+        );
+        Self::with_period_dw((period, T::zero()))
+    }
+
+    /// Create a new phase accumulator at phase zero with period $2\pi$, for angles in radians.
+    pub fn radians() -> Self {
+        let hi = T::from(std::f64::consts::TAU).unwrap();
+        // `2π - fl64(2π)`, added to the exact difference between the `f64` value and `hi`.
+        let lo = T::from((std::f64::consts::TAU - hi.to_f64().unwrap()) + 2.4492935982947064e-16)
+            .unwrap();
+        Self::with_period_dw((hi, lo))
+    }
+
+    /// Create a new phase accumulator at phase zero with period one, for angles in turns.
+    pub fn turns() -> Self {
+        Self::new(T::one())
+    }
+
+    fn with_period_dw(period: (T, T)) -> Self {
+        Self {
+            phase: (T::zero(), T::zero()),
+            period,
+            wraps: 0,
+        }
+    }
+
+    /// Get the period, rounded to nearest.
+    pub fn period(&self) -> T {
+        self.period.0 + self.period.1
+    }
+
+    /// Advance the phase by `dx`, which may be negative.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dx` is not finite, or if the number of complete turns overflows an [`i64`].
+    pub fn advance(&mut self, dx: T) {
+        assert!(dx.is_finite(), "the increment must be finite");
+        self.phase = add_dw(self.phase, dx);
+        let k = (self.phase.0 / self.period.0).floor();
+        if k != T::zero() {
+            self.sub_periods(k);
+        }
+        // The quotient may be off by one, and the double-words are normalized,
+        // hence the sign of the high-order part is the sign of the phase.
+        if self.phase.0 < T::zero() {
+            self.sub_periods(-T::one());
+        } else {
+            let (d, _) = add_dw(add_dw(self.phase, -self.period.0), -self.period.1);
+            if d >= T::zero() {
+                self.sub_periods(T::one());
+            }
+        }
+    }
+
+    // Subtract `k` periods from the phase, `k` being an integer.
+    fn sub_periods(&mut self, k: T) {
+        let wraps = k
+            .to_i64()
+            .and_then(|k| self.wraps.checked_add(k))
+            .expect("the number of turns overflows `i64`");
+        let (p, δp) = two_prod(k, self.period.0);
+        self.phase = add_dw(add_dw(add_dw(self.phase, -p), -δp), -(k * self.period.1));
+        self.wraps = wraps;
+    }
+
+    /// Get the current phase in $\[0,P\]$, rounded to nearest.
+    ///
+    /// The exact phase is less than the period $P$, but it may round to it.
+    pub fn phase(&self) -> T {
+        self.phase.0 + self.phase.1
+    }
+
+    /// Get the current phase as a double-word $(x_h,x_l)$, where $x_h$ is the current phase rounded to nearest.
+    pub fn phase_dw(&self) -> (T, T) {
+        self.phase
+    }
+
+    /// Get the current phase in $\[-P/2,P/2\]$, rounded to nearest.
+    pub fn centered_phase(&self) -> T {
+        let half = T::from(0.5).unwrap();
+        let (h, l) = self.phase;
+        let (ph, pl) = self.period;
+        // Subtracting half a period is exact.
+        let (d, _) = add_dw(add_dw((h, l), -ph * half), -pl * half);
+        if d < T::zero() {
+            h + l
+        } else {
+            let (c, δc) = add_dw(add_dw((h, l), -ph), -pl);
+            c + δc
+        }
+    }
+
+    /// Get the number of complete turns, that is the integer $k$ such that
+    /// the sum of the increments is $kP$ plus the phase.
+    pub fn wraps(&self) -> i64 {
+        self.wraps
+    }
+}
+
+impl<T: Float> AddAssign<T> for PhaseAccumulator<T> {
+    fn add_assign(&mut self, rhs: T) {
+        self.advance(rhs);
+    }
+}
+
+impl<T: Float> AddAssign<&T> for PhaseAccumulator<T> {
+    fn add_assign(&mut self, rhs: &T) {
+        self.advance(*rhs);
+    }
+}
+
+impl<T: Float, V> Add<V> for PhaseAccumulator<T>
+where
+    Self: AddAssign<V>,
+{
+    type Output = Self;
+    fn add(mut self, rhs: V) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn turns_no_drift() {
+        for dx in [0.1f32, -1.0 / 3.0, 1e-3, 0.7] {
+            let mut phase = PhaseAccumulator::turns();
+            let n = 1_000_000;
+            for _ in 0..n {
+                phase += dx;
+            }
+            let exact = n as f64 * dx as f64;
+            assert_eq!(phase.wraps(), exact.floor() as i64);
+            let (h, l) = phase.phase_dw();
+            assert!(h + l >= 0.0 && h + l <= 1.0);
+            assert_eq!(h as f64 + l as f64, exact - exact.floor());
+        }
+    }
+
+    #[test]
+    fn radians_vs_f64() {
+        use rand::prelude::*;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        // With `f32` increments on a grid of `2^-30`, the exact angle is known in `f64`.
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+        let mut phase = PhaseAccumulator::<f32>::radians();
+        let mut naive = 0f32;
+        let mut exact = 0f64;
+        for _ in 0..100_000 {
+            let dx = rng.gen_range(-(1 << 28)..1 << 30) as f32 * 2f32.powi(-30);
+            phase += dx;
+            naive = (naive + dx).rem_euclid(std::f32::consts::TAU);
+            exact += dx as f64;
+        }
+        let tau = (std::f64::consts::TAU, 2.4492935982947064e-16);
+        let k = (exact / tau.0).floor();
+        let reduced = exact - k * tau.0 - k * tau.1;
+        assert_eq!(phase.wraps(), k as i64);
+        let tol = 2.0 * f32::EPSILON as f64;
+        assert!((phase.phase() as f64 - reduced).abs() <= tol);
+        assert!((naive as f64 - reduced).abs() > 100.0 * tol);
+    }
+
+    #[test]
+    fn phase_wraps() {
+        let mut phase = PhaseAccumulator::new(360.0);
+        phase += 350.0;
+        assert_eq!(phase.centered_phase(), -10.0);
+        phase += 20.0;
+        assert_eq!(phase.phase(), 10.0);
+        assert_eq!(phase.centered_phase(), 10.0);
+        assert_eq!(phase.wraps(), 1);
+        phase += -740.0;
+        assert_eq!(phase.phase(), 350.0);
+        assert_eq!(phase.wraps(), -2);
+        let phase = phase + 10.0;
+        assert_eq!((phase.phase(), phase.wraps()), (0.0, -1));
+        assert_eq!(
+            PhaseAccumulator::<f64>::radians().period(),
+            std::f64::consts::TAU
+        );
+    }
+
+    #[test]
+    fn phase_huge() {
+        let mut phase = PhaseAccumulator::turns();
+        phase += 2f64.powi(62) + 2048.0;
+        phase += 0.25;
+        assert_eq!(phase.wraps(), (1 << 62) + 2048);
+        assert_eq!(phase.phase(), 0.25);
+        phase += -2f64.powi(62);
+        assert_eq!(phase.wraps(), 2048);
+    }
+
+    #[test]
+    fn phase_max_wraps() {
+        // The number of turns is only updated on an actual wrap.
+        let mut phase = PhaseAccumulator::turns();
+        phase.wraps = i64::MAX;
+        phase += 0.25;
+        phase += 0.5;
+        phase += -0.75;
+        assert_eq!((phase.phase(), phase.wraps()), (0.0, i64::MAX));
+    }
+
+    #[test]
+    #[should_panic]
+    fn phase_overflow() {
+        PhaseAccumulator::turns().advance(1e30);
+    }
+
+    #[test]
+    #[should_panic]
+    fn phase_infinite() {
+        PhaseAccumulator::radians().advance(f64::INFINITY);
+    }
+
+    #[test]
+    #[should_panic]
+    fn phase_nan() {
+        PhaseAccumulator::radians().advance(f32::NAN);
+    }
+
+    #[test]
+    #[should_panic]
+    fn phase_period() {
+        PhaseAccumulator::new(0.0);
+    }
+}
@@ -0,0 +1,444 @@
+use crate::*;
+
+/// Compensated Horner scheme, see Graillat, Langlois, Louvet, *Algorithms for accurate, validated and fast polynomial evaluation* (2009).
+///
+/// **Input:** the coefficients $a_0,\dots,a_n$ of a polynomial $p(x)=\sum_i a_i x^i$, in order of increasing degree, and a floating-point number $x$.
+///
+/// **Output:** the value $p(x)$, computed as if in twice the working precision and then rounded back to working precision.
+///
+/// The rounding errors of each multiplication and addition of the Horner scheme are computed with [`two_prod`] and [`two_sum`]
+/// and, in turn, are evaluated with the Horner scheme as a correction term, which is added back at the end.
+///
+/// See also [`comp_horner_dw`] for the result as a double-word and [`comp_horner_with_bound`] for an error bound.
+///
+/// # Examples
+///
+/// Evaluate $(x-1)^3=x^3-3x^2+3x-1$ near its triple root.
+///
+/// ```
+/// # use compensated_summation::comp_horner;
+/// let x: f64 = 1.0 + 1e-5;
+/// let p = [-1.0, 3.0, -3.0, 1.0];
+/// let exact = (x - 1.0) * (x - 1.0) * (x - 1.0);
+/// assert!((comp_horner(&p, x) - exact).abs() <= exact * 1e-15);
+/// ```
+pub fn comp_horner<T: Float>(coeffs: &[T], x: T) -> T {
+    let (s, c) = horner_eft(coeffs, x, |_, _| ());
+    s + c
+}
+
+/// Compensated Horner scheme, with the result as a double-word.
+///
+/// **Input:** the coefficients $a_0,\dots,a_n$ of a polynomial $p(x)=\sum_i a_i x^i$, in order of increasing degree, and a floating-point number $x$.
+///
+/// **Output:** a tuple $(s,t)$ such that $s=s\oplus t$ and $s+t$ approximates $p(x)$ as if computed in twice the working precision.
+///
+/// This is the same algorithm as [`comp_horner`], but the final rounding is replaced by [`two_sum`],
+/// so that the result can be used in subsequent double-word computations.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::comp_horner_dw;
+/// // 1 + x + x^2 at x = 2^-30 is 1 + 2^-30 + 2^-60, which is not representable in `f64`.
+/// let x = 2f64.powi(-30);
+/// assert_eq!(comp_horner_dw(&[1.0, 1.0, 1.0], x), (1.0 + x, x * x));
+/// ```
+pub fn comp_horner_dw<T: Float>(coeffs: &[T], x: T) -> (T, T) {
+    let (s, c) = horner_eft(coeffs, x, |_, _| ());
+    two_sum(s, c)
+}
+
+/// Compensated Horner scheme, with an a posteriori error bound.
+///
+/// **Input:** the coefficients $a_0,\dots,a_n$ of a polynomial $p(x)=\sum_i a_i x^i$, in order of increasing degree, and a floating-point number $x$.
+///
+/// **Output:** a tuple $(r,\beta)$ where $r$ is the same as returned by [`comp_horner`] and $\beta$ is an upper bound of the error $|r-p(x)|$,
+/// see Langlois, Louvet, *How to ensure a faithful polynomial evaluation with the compensated Horner algorithm* (2007).
+///
+/// The bound is computed in working precision, assuming rounding to nearest and no underflow.
+/// If $\beta<u|r|$, where $u$ is the unit roundoff, then $r$ is a faithful rounding of $p(x)$.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::comp_horner_with_bound;
+/// let x: f64 = 1.0 + 1e-5;
+/// let p = [-1.0, 3.0, -3.0, 1.0];
+/// let exact = (x - 1.0) * (x - 1.0) * (x - 1.0);
+/// let (r, bound) = comp_horner_with_bound(&p, x);
+/// assert!((r - exact).abs() <= bound);
+/// ```
+pub fn comp_horner_with_bound<T: Float>(coeffs: &[T], x: T) -> (T, T) {
+    let ax = x.abs();
+    let mut b = T::zero();
+    let (s, c) = horner_eft(coeffs, x, |π, σ| b = b * ax + (π.abs() + σ.abs()));
+    let r = s + c;
+    let n = T::from(coeffs.len().saturating_sub(1)).unwrap();
+    let u = T::epsilon() / (T::one() + T::one());
+    let two = T::one() + T::one();
+    let four = two + two;
+    let gamma = |k: T| (k * u) / (T::one() - k * u);
+    let β =
+        (gamma(four * n + two) * b + two * u * u * r.abs()) / (T::one() - two * (n + T::one()) * u);
+    (r, u * r.abs() + β)
+}
+
+// Horner scheme with error-free transformations: returns the floating-point result and the
+// compensation term, calling `f(π, σ)` with the errors of each step (from the highest degree).
+fn horner_eft<T: Float>(coeffs: &[T], x: T, mut f: impl FnMut(T, T)) -> (T, T) {
+    let Some((&last, rest)) = coeffs.split_last() else {
+        return (T::zero(), T::zero());
+    };
+    let mut s = last;
+    let mut c = T::zero();
+    for &a in rest.iter().rev() {
+        let (p, π) = two_prod(s, x);
+        let (t, σ) = two_sum(p, a);
+        s = t;
+        c = c.mul_add(x, π + σ);
+        f(π, σ);
+    }
+    (s, c)
+}
+
+/// Compensated Clenshaw algorithm for Chebyshev series.
+///
+/// **Input:** the coefficients $c_0,\dots,c_n$ of a Chebyshev series $f(x)=\sum_k c_k T_k(x)$ and a floating-point number $x$.
+///
+/// **Output:** the value $f(x)$, computed as if in twice the working precision and then rounded back to working precision.
+///
+/// The Clenshaw recurrence $b_k=c_k+2xb_{k+1}-b_{k+2}$ is computed with [`two_prod`] and [`two_sum`],
+/// and the rounding errors of each step are propagated with the same recurrence in a correction term,
+/// which is added back at the end (like [`comp_horner`] does for the Horner scheme).
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::comp_clenshaw_chebyshev;
+/// // T_3(x) = 4x^3 - 3x
+/// assert_eq!(comp_clenshaw_chebyshev(&[0.0, 0.0, 0.0, 1.0], 0.5), -1.0);
+/// ```
+pub fn comp_clenshaw_chebyshev<T: Float>(coeffs: &[T], x: T) -> T {
+    let two_x = x + x;
+    comp_clenshaw(coeffs, |_| two_x, |_| -T::one(), T::one(), x)
+}
+
+/// Compensated Clenshaw algorithm for series of functions satisfying a three-term recurrence.
+///
+/// **Input:** the coefficients $c_0,\dots,c_n$ of a series $f(x)=\sum_k c_k\phi_k(x)$,
+/// where the functions satisfy $\phi_{k+1}(x)=\alpha_k(x)\phi_k(x)+\beta_k(x)\phi_{k-1}(x)$ for $k\geq1$;
+/// the values $\alpha_k(x)$ and $\beta_k(x)$ are given by the closures `alpha(k)` and `beta(k)`,
+/// and the values $\phi_0(x)$ and $\phi_1(x)$ by `phi0` and `phi1`.
+///
+/// **Output:** the value $f(x)$, computed as if in twice the working precision and then rounded back to working precision.
+///
+/// The Clenshaw recurrence $b_k=c_k+\alpha_k b_{k+1}+\beta_{k+1}b_{k+2}$ is computed with [`two_prod`] and [`two_sum`],
+/// and the rounding errors of each step are propagated with the same recurrence in a correction term.
+/// Note that the values returned by `alpha`, `beta`, `phi0` and `phi1` are taken as exact:
+/// the errors committed in computing them are not compensated.
+///
+/// # Examples
+///
+/// Evaluate a Legendre series, using Bonnet's recursion formula
+/// $P_{k+1}(x)=\frac{2k+1}{k+1}xP_k(x)-\frac{k}{k+1}P_{k-1}(x)$.
+///
+/// ```
+/// # use compensated_summation::comp_clenshaw;
+/// let x = 0.5;
+/// let alpha = |k: usize| (2 * k + 1) as f64 / (k + 1) as f64 * x;
+/// let beta = |k: usize| -(k as f64) / (k + 1) as f64;
+/// // P_2(x) = (3x^2 - 1) / 2
+/// assert_eq!(comp_clenshaw(&[0.0, 0.0, 1.0], alpha, beta, 1.0, x), -0.125);
+/// ```
+pub fn comp_clenshaw<T: Float>(
+    coeffs: &[T],
+    alpha: impl Fn(usize) -> T,
+    beta: impl Fn(usize) -> T,
+    phi0: T,
+    phi1: T,
+) -> T {
+    let zero = (T::zero(), T::zero());
+    let Some((&c0, rest)) = coeffs.split_first() else {
+        return T::zero();
+    };
+    let mut b1 = zero;
+    let mut b2 = zero;
+    for (i, &c) in rest.iter().enumerate().rev() {
+        let k = i + 1;
+        let b = comp_step(c, alpha(k), b1, beta(k + 1), b2);
+        b2 = b1;
+        b1 = b;
+    }
+    // f = φ0 (c0 + β1 b2) + φ1 b1
+    let u = comp_step(c0, beta(1), b2, T::zero(), zero);
+    let (h, l) = comp_step(T::zero(), phi0, u, phi1, b1);
+    h + l
+}
+
+/// Compensated de Casteljau algorithm for polynomials in Bernstein form,
+/// see Jiang, Li, Cheng, Su, *Accurate evaluation of a polynomial and its derivative in Bernstein form* (2010).
+///
+/// **Input:** the Bernstein coefficients $b_0,\dots,b_n$ of a polynomial $p(t)=\sum_k b_k\binom nk(1-t)^{n-k}t^k$ and a floating-point number $t$ (typically in $\[0,1\]$).
+///
+/// **Output:** the value $p(t)$, computed as if in twice the working precision and then rounded back to working precision.
+///
+/// Each convex combination $(1-t)b_i+tb_{i+1}$ of the de Casteljau algorithm is computed with [`two_prod`] and [`two_sum`]
+/// (and so is $1-t$), and the rounding errors are propagated with the same algorithm in a correction term,
+/// which is added back at the end.
+///
+/// See [`comp_de_casteljau_points`] for Bézier curves with control points in $D$ dimensions.
+///
+/// # Examples
+///
+/// The Bernstein coefficients of $(1-2t)^n$ are $b_k=(-1)^k$.
+///
+/// ```
+/// # use compensated_summation::comp_de_casteljau;
+/// let t: f64 = 0.5 + 1e-4;
+/// let exact = (1.0 - 2.0 * t).powi(7);
+/// let p = comp_de_casteljau(&[1.0, -1.0, 1.0, -1.0, 1.0, -1.0, 1.0, -1.0], t);
+/// assert!((p - exact).abs() <= exact.abs() * 1e-10);
+/// ```
+pub fn comp_de_casteljau<T: Float>(coeffs: &[T], t: T) -> T {
+    let mut b: Vec<(T, T)> = coeffs.iter().map(|&c| (c, T::zero())).collect();
+    de_casteljau_eft(&mut b, t)
+}
+
+/// Compensated de Casteljau algorithm for Bézier curves.
+///
+/// **Input:** the control points $P_0,\dots,P_n\in\mathbb R^D$ of a Bézier curve $B(t)=\sum_k P_k\binom nk(1-t)^{n-k}t^k$ and a floating-point number $t$ (typically in $\[0,1\]$).
+///
+/// **Output:** the point $B(t)$, each of whose coordinates is computed with [`comp_de_casteljau`].
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::comp_de_casteljau_points;
+/// let points = [[0.0, 0.0], [1.0, 2.0], [2.0, 0.0]];
+/// assert_eq!(comp_de_casteljau_points(&points, 0.5), [1.0, 1.0]);
+/// ```
+pub fn comp_de_casteljau_points<T: Float, const D: usize>(points: &[[T; D]], t: T) -> [T; D] {
+    let mut b = Vec::with_capacity(points.len());
+    std::array::from_fn(|d| {
+        b.clear();
+        b.extend(points.iter().map(|p| (p[d], T::zero())));
+        de_casteljau_eft(&mut b, t)
+    })
+}
+
+// De Casteljau algorithm with error-free transformations, performed in place on pairs
+// (value, correction).
+fn de_casteljau_eft<T: Float>(b: &mut [(T, T)], t: T) -> T {
+    if b.is_empty() {
+        return T::zero();
+    }
+    let (r, δr) = two_sum(T::one(), -t);
+    for n in (1..b.len()).rev() {
+        for i in 0..n {
+            let (h, l) = comp_step(T::zero(), r, b[i], t, b[i + 1]);
+            b[i] = (h, δr.mul_add(b[i].0, l));
+        }
+    }
+    b[0].0 + b[0].1
+}
+
+// Compute `c + a * x + b * y`, where `x` and `y` are given as pairs (value, correction),
+// returning the floating-point result and the propagated correction plus the new rounding errors.
+fn comp_step<T: Float>(c: T, a: T, (xh, xl): (T, T), b: T, (yh, yl): (T, T)) -> (T, T) {
+    let (p, δp) = two_prod(a, xh);
+    let (q, δq) = two_prod(b, yh);
+    let (s, δs) = two_sum(p, q);
+    let (h, δh) = two_sum(c, s);
+    let l = a.mul_add(xl, b.mul_add(yl, (δp + δq) + (δs + δh)));
+    (h, l)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    /// Coefficients of $(x-2)^n$, in order of increasing degree, which are exact in `f64` for small $n$.
+    fn binomial(n: i32) -> Vec<f64> {
+        let mut c = vec![1.0];
+        for _ in 0..n {
+            let mut d = vec![0.0; c.len() + 1];
+            for (i, &ci) in c.iter().enumerate() {
+                d[i] -= 2.0 * ci;
+                d[i + 1] += ci;
+            }
+            c = d;
+        }
+        c
+    }
+
+    fn horner(coeffs: &[f64], x: f64) -> f64 {
+        coeffs.iter().rev().fold(0.0, |s, &a| s * x + a)
+    }
+
+    #[test]
+    fn comp_horner_near_root() {
+        let p = binomial(7);
+        for i in 1..=10 {
+            let x = 2.0 + i as f64 * 1e-3;
+            let exact = (x - 2.0).powi(7);
+            let r = comp_horner(&p, x);
+            // The compensated result is accurate as if computed in twice the working precision,
+            // whereas the naive one has no correct digits.
+            assert!((r - exact).abs() <= exact * 1e-8);
+            assert!((horner(&p, x) - exact).abs() >= exact);
+        }
+    }
+
+    #[test]
+    fn comp_horner_bound() {
+        for n in [3, 5, 7, 9] {
+            let p = binomial(n);
+            for i in -50..=50 {
+                let x = 2.0 + i as f64 * 1e-3;
+                let exact = (x - 2.0).powi(n);
+                let (r, bound) = comp_horner_with_bound(&p, x);
+                assert_eq!(r, comp_horner(&p, x));
+                // Account for the error of the reference value.
+                assert!((r - exact).abs() <= bound + exact.abs() * n as f64 * f64::EPSILON);
+            }
+        }
+    }
+
+    #[test]
+    fn comp_horner_dw_sum() {
+        let p = binomial(5);
+        let x = 2.0 + 1e-3;
+        let (s, t) = comp_horner_dw(&p, x);
+        assert_eq!(s, comp_horner(&p, x));
+        assert_eq!(s + t, s);
+    }
+
+    fn de_casteljau(coeffs: &[f64], t: f64) -> f64 {
+        let mut b = coeffs.to_vec();
+        for n in (1..b.len()).rev() {
+            for i in 0..n {
+                b[i] = (1.0 - t) * b[i] + t * b[i + 1];
+            }
+        }
+        b[0]
+    }
+
+    #[test]
+    fn comp_de_casteljau_near_root() {
+        let coeffs: Vec<f64> = (0..=9)
+            .map(|k| if k % 2 == 0 { 1.0 } else { -1.0 })
+            .collect();
+        for i in 1..=10 {
+            let t = 0.5 + i as f64 * 1e-7;
+            let exact = (1.0 - 2.0 * t).powi(9);
+            let p = comp_de_casteljau(&coeffs, t);
+            assert!((p - exact).abs() <= exact.abs() * 1e-14);
+            assert!((de_casteljau(&coeffs, t) - exact).abs() >= exact.abs() * 1e-12);
+        }
+    }
+
+    #[test]
+    fn comp_de_casteljau_endpoints() {
+        let coeffs = [0.1, 0.7, -0.3, 0.2];
+        assert_eq!(comp_de_casteljau(&coeffs, 0.0), 0.1);
+        assert_eq!(comp_de_casteljau(&coeffs, 1.0), 0.2);
+        assert_eq!(comp_de_casteljau::<f64>(&[], 0.5), 0.0);
+    }
+
+    #[test]
+    fn comp_de_casteljau_points_coordinates() {
+        let points = [
+            [0.1, 1.0, -2.0],
+            [0.7, 3.0, 0.5],
+            [-0.3, 0.0, 0.25],
+            [0.2, 1.0, 1.0],
+        ];
+        for i in 0..=10 {
+            let t = i as f64 / 10.0;
+            let b = comp_de_casteljau_points(&points, t);
+            for d in 0..3 {
+                let coeffs: Vec<f64> = points.iter().map(|p| p[d]).collect();
+                assert_eq!(b[d], comp_de_casteljau(&coeffs, t));
+            }
+        }
+    }
+
+    /// Naive Clenshaw algorithm for Chebyshev series.
+    fn clenshaw<T: num_traits::Float>(coeffs: &[T], x: T) -> T {
+        let (mut b1, mut b2) = (T::zero(), T::zero());
+        for &c in coeffs[1..].iter().rev() {
+            (b1, b2) = (c + (x + x) * b1 - b2, b1);
+        }
+        coeffs[0] + x * b1 - b2
+    }
+
+    #[test]
+    fn comp_clenshaw_chebyshev_accuracy() {
+        use rand::prelude::*;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        // Use `f64` as a reference for `f32`, near the endpoints of the interval.
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+        let mut better = 0;
+        for _ in 0..1000 {
+            let coeffs: Vec<f32> = (0..30).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            let x: f32 = rng.gen_range(0.999..1.0);
+            let coeffs64: Vec<f64> = coeffs.iter().map(|&c| c as f64).collect();
+            let reference = clenshaw(&coeffs64, x as f64);
+            let naive = clenshaw(&coeffs, x) as f64;
+            let comp = comp_clenshaw_chebyshev(&coeffs, x) as f64;
+            let cond: f64 = coeffs64.iter().map(|c| c.abs()).sum();
+            assert!((comp - reference).abs() <= reference.abs() * 1e-7 + cond * 1e-12);
+            if (comp - reference).abs() < (naive - reference).abs() {
+                better += 1;
+            }
+        }
+        assert!(better > 500);
+    }
+
+    #[test]
+    fn comp_clenshaw_chebyshev_cos() {
+        // T_n(cos θ) = cos(nθ)
+        for n in 0..50 {
+            let mut coeffs = vec![0.0; n + 1];
+            coeffs[n] = 1.0;
+            for i in 0..=20 {
+                let θ = i as f64 * std::f64::consts::PI / 20.0;
+                let x = θ.cos();
+                let exact = (n as f64 * x.acos()).cos();
+                assert!((comp_clenshaw_chebyshev(&coeffs, x) - exact).abs() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn comp_clenshaw_generic() {
+        use rand::prelude::*;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+        for _ in 0..100 {
+            let coeffs: Vec<f64> = (0..20).map(|_| rng.gen_range(-1.0..1.0)).collect();
+            let x = rng.gen_range(-1.0..1.0);
+            // Chebyshev polynomials of the first kind, with the general recurrence.
+            assert_eq!(
+                comp_clenshaw(&coeffs, |_| 2.0 * x, |_| -1.0, 1.0, x),
+                comp_clenshaw_chebyshev(&coeffs, x)
+            );
+            // Monomials, for which the Clenshaw algorithm reduces to the Horner scheme.
+            let monomial = comp_clenshaw(&coeffs, |_| x, |_| 0.0, 1.0, x);
+            assert!((monomial - comp_horner(&coeffs, x)).abs() <= 1e-15);
+        }
+        assert_eq!(comp_clenshaw(&[], |_| 1.0, |_| 1.0, 1.0, 1.0), 0.0);
+        assert_eq!(comp_clenshaw(&[3.0], |_| 1.0, |_| 1.0, 2.0, 1.0), 6.0);
+    }
+
+    #[test]
+    fn comp_horner_trivial() {
+        assert_eq!(comp_horner::<f64>(&[], 3.0), 0.0);
+        assert_eq!(comp_horner(&[5.0], 3.0), 5.0);
+        assert_eq!(comp_horner(&[1.0, 2.0, 3.0], 10.0), 321.0);
+        assert_eq!(comp_horner_with_bound(&[5.0], 3.0).0, 5.0);
+    }
+}
@@ -0,0 +1,251 @@
+use crate::*;
+use std::iter::Product;
+use std::ops::{Div, DivAssign, Mul, MulAssign};
+
+/// This type is an accumulator for computing a product with the compensated product algorithm,
+/// see Graillat, *Accurate floating-point product and exponentiation* (2009).
+///
+/// The generic parameter `T` should typically implement [`num_traits::float::Float`] and can usually be inferred.
+///
+/// The running product is represented as $(\mathrm{prod}+\mathrm{comp})\cdot2^\mathrm{exp}$,
+/// where $1\leq|\mathrm{prod}|<2$ and $\mathrm{comp}$ is the compensation of the error
+/// (hence, relatively to $\mathrm{prod}$, it is the relative error of the product).
+/// Each factor is split into its mantissa and exponent: the mantissas are multiplied with [`two_prod`],
+/// and the exponents are added in the separate integer $\mathrm{exp}$, so that the running product
+/// never overflows or underflows.
+///
+/// # Examples
+///
+/// You can create a new empty accumulator with [`CompensatedProduct::new()`];
+/// then you can multiply and divide it by floating-point numbers;
+/// when you are done, you can retrieve the total with the [`CompensatedProduct::total()`] method.
+///
+/// ```
+/// # use compensated_summation::CompensatedProduct;
+/// let mut prod = CompensatedProduct::new();
+/// prod *= 1e300;
+/// prod *= 1e300;
+/// prod /= 1e300;
+/// assert_eq!(prod.total(), 1e300);
+/// ```
+///
+/// In addition, [`CompensatedProduct`] implements the [`std::iter::Product`](#impl-Product<V>-for-CompensatedProduct<T>) trait,
+/// which means that an iterator of floating-point numbers can be multiplied with its [`Iterator::product()`] method.
+/// Even if the product itself is not representable, its logarithm can be retrieved with [`CompensatedProduct::ln()`].
+///
+/// ```
+/// # use compensated_summation::CompensatedProduct;
+/// let likelihood = [1e-200; 10].iter().product::<CompensatedProduct<f64>>();
+/// assert_eq!(likelihood.total(), 0.0);
+/// assert!((likelihood.ln() + 2000.0 * 10f64.ln()).abs() < 1e-12);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct CompensatedProduct<T> {
+    /// Accumulated product, normalized so that `1 <= |prod| < 2` (unless zero or non-finite).
+    pub prod: T,
+    /// Compensation of the error.
+    pub comp: T,
+    /// Binary exponent of the product.
+    pub exp: i64,
+}
+
+impl<T: Float> CompensatedProduct<T> {
+    /// Create a new empty accumulator, whose total is one.
+    pub fn new() -> Self {
+        Self {
+            prod: T::one(),
+            comp: T::zero(),
+            exp: 0,
+        }
+    }
+
+    /// Get the estimated total product.
+    ///
+    /// The result overflows to infinity or underflows to zero if the product is not representable.
+    pub fn total(&self) -> T {
+        ldexp(self.prod + self.comp, self.exp)
+    }
+
+    /// Get the natural logarithm of the estimated total product.
+    ///
+    /// This is finite whenever the product is finite and positive, even if it is not representable.
+    pub fn ln(&self) -> T {
+        let ln2 = T::from(std::f64::consts::LN_2).unwrap();
+        (self.prod + self.comp).ln() + T::from(self.exp).unwrap() * ln2
+    }
+
+    // Whether the product is finite and nonzero, so that the algorithm applies.
+    fn is_normal(&self) -> bool {
+        self.prod.is_finite() && self.prod != T::zero()
+    }
+
+    // Restore the invariant `1 <= |prod| < 2`, given that `1/2 <= |prod| < 4`.
+    fn normalize(&mut self) {
+        let two = T::one() + T::one();
+        if self.prod.abs() >= two {
+            self.prod = self.prod / two;
+            self.comp = self.comp / two;
+            self.exp += 1;
+        } else if self.prod.abs() < T::one() && self.prod != T::zero() {
+            self.prod = self.prod * two;
+            self.comp = self.comp * two;
+            self.exp -= 1;
+        }
+    }
+}
+
+impl<T: Float> Default for CompensatedProduct<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> MulAssign<T> for CompensatedProduct<T> {
+    fn mul_assign(&mut self, rhs: T) {
+        if !self.is_normal() || rhs == T::zero() || !rhs.is_finite() {
+            self.prod = self.prod * rhs;
+            self.comp = T::zero();
+            return;
+        }
+        let (m, e) = frexp(rhs);
+        let (p, c) = two_prod(self.prod, m);
+        self.prod = p;
+        self.comp = self.comp.mul_add(m, c);
+        self.exp += e;
+        self.normalize();
+    }
+}
+
+impl<T: Float> DivAssign<T> for CompensatedProduct<T> {
+    fn div_assign(&mut self, rhs: T) {
+        if !self.is_normal() || rhs == T::zero() || !rhs.is_finite() {
+            self.prod = self.prod / rhs;
+            self.comp = T::zero();
+            return;
+        }
+        let (m, e) = frexp(rhs);
+        (self.prod, self.comp) = div_dw((self.prod, self.comp), m);
+        self.exp -= e;
+        self.normalize();
+    }
+}
+
+impl<T: Float> MulAssign<&T> for CompensatedProduct<T> {
+    fn mul_assign(&mut self, rhs: &T) {
+        *self *= *rhs;
+    }
+}
+
+impl<T: Float> DivAssign<&T> for CompensatedProduct<T> {
+    fn div_assign(&mut self, rhs: &T) {
+        *self /= *rhs;
+    }
+}
+
+impl<T: Float, V> Mul<V> for CompensatedProduct<T>
+where
+    Self: MulAssign<V>,
+{
+    type Output = Self;
+    fn mul(mut self, rhs: V) -> Self::Output {
+        self *= rhs;
+        self
+    }
+}
+
+impl<T: Float, V> Div<V> for CompensatedProduct<T>
+where
+    Self: DivAssign<V>,
+{
+    type Output = Self;
+    fn div(mut self, rhs: V) -> Self::Output {
+        self /= rhs;
+        self
+    }
+}
+
+impl<T: Float, V> Product<V> for CompensatedProduct<T>
+where
+    Self: MulAssign<V>,
+{
+    fn product<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = V>,
+    {
+        let mut prod = CompensatedProduct::new();
+        for x in iter {
+            prod *= x;
+        }
+        prod
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn product_vs_f64() {
+        use rand::prelude::*;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        // The product of a few `f32` numbers is computed almost exactly in `f64`.
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+        for _ in 0..1000 {
+            let values: Vec<f32> = (0..20).map(|_| rng.gen_range(0.5..2.0)).collect();
+            let reference = values.iter().map(|&x| x as f64).product::<f64>() as f32;
+            let naive = values.iter().product::<f32>();
+            let comp = values.iter().product::<CompensatedProduct<f32>>().total();
+            assert!((comp - reference).abs() <= reference * f32::EPSILON / 2.0);
+            assert!((comp - reference).abs() <= (naive - reference).abs());
+        }
+    }
+
+    #[test]
+    fn product_division() {
+        use rand::prelude::*;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+        for _ in 0..1000 {
+            let x: f32 = rng.gen_range(-4.0..4.0);
+            let y: f32 = rng.gen_range(-4.0..4.0);
+            let z: f32 = rng.gen_range(0.1..4.0);
+            let reference = (x as f64 * y as f64 / z as f64) as f32;
+            let comp = (CompensatedProduct::new() * x * y / z).total();
+            assert_eq!(comp, reference);
+        }
+    }
+
+    #[test]
+    fn product_exponent() {
+        let prod = [1e300, 1e300, 1e300, 1e-300, 1e-300, 1e-300]
+            .iter()
+            .product::<CompensatedProduct<f64>>();
+        assert!((prod.total() - 1.0).abs() <= f64::EPSILON);
+        assert_eq!(prod.exp, 0);
+        assert_eq!(
+            [2.0; 2000].iter().product::<CompensatedProduct<f64>>().exp,
+            2000
+        );
+        assert_eq!(
+            [2.0; 2000]
+                .iter()
+                .product::<CompensatedProduct<f64>>()
+                .total(),
+            f64::INFINITY
+        );
+    }
+
+    #[test]
+    fn product_special() {
+        assert_eq!((CompensatedProduct::new() * 3.0 * 0.0 * 5.0).total(), 0.0);
+        assert_eq!(
+            (CompensatedProduct::new() * -3.0 * f64::INFINITY).total(),
+            f64::NEG_INFINITY
+        );
+        assert!((CompensatedProduct::new() * 0.0 / 0.0).total().is_nan());
+        assert_eq!(CompensatedProduct::<f64>::new().total(), 1.0);
+        assert_eq!((CompensatedProduct::new() * -0.75).total(), -0.75);
+    }
+}
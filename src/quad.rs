@@ -0,0 +1,238 @@
+use crate::*;
+
+// Add the exact product `w * y` to the accumulator.
+fn add_prod<T: Float>(acc: &mut KahanBabuskaNeumaier<T>, w: T, y: T) {
+    let (p, δp) = two_prod(w, y);
+    *acc += p;
+    *acc += δp;
+}
+
+/// Compensated trapezoidal rule for evenly spaced samples.
+///
+/// **Input:** the samples $y_0,\dots,y_{n-1}$ of a function at evenly spaced points, and the spacing $\Delta x$.
+///
+/// **Output:** the approximation $\Delta x\left(\frac{y_0}2+y_1+\dots+y_{n-2}+\frac{y_{n-1}}2\right)$ of the integral,
+/// which is zero if $n<2$.
+///
+/// The sum of the samples with weights $1,2,\dots,2,1$ is accumulated with [`KahanBabuskaNeumaier`]
+/// (the products by the weights being exact), and multiplied by $\Delta x/2$ with [`two_prod`],
+/// hence the result is affected by the discretization error only.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::trapezoid;
+/// let y = [0.0, 1e16, 1.0, -1e16, 0.0];
+/// assert_eq!(trapezoid(&y, 0.5), 0.5);
+/// ```
+pub fn trapezoid<T: Float>(y: &[T], dx: T) -> T {
+    let n = y.len();
+    if n < 2 {
+        return T::zero();
+    }
+    let two = T::one() + T::one();
+    let mut acc = KahanBabuskaNeumaier::new();
+    acc += y[0];
+    for &yi in &y[1..n - 1] {
+        acc += yi * two;
+    }
+    acc += y[n - 1];
+    let (h, l) = mul_dw((acc.sum, acc.comp), dx / two);
+    h + l
+}
+
+/// Compensated trapezoidal rule for arbitrarily spaced samples.
+///
+/// **Input:** the points $x_0,\dots,x_{n-1}$ and the samples $y_0,\dots,y_{n-1}$ of a function at these points.
+///
+/// **Output:** the approximation $\sum_i(x_{i+1}-x_i)\frac{y_i+y_{i+1}}2$ of the integral, which is zero if $n<2$.
+///
+/// The width of each interval and the sum of the samples at its ends are computed exactly with [`two_sum`],
+/// and their product is accumulated with a [`DotAccumulator`], together with the first-order terms of the errors.
+///
+/// # Panics
+///
+/// Panics if `x` and `y` have different lengths.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::trapezoid_xy;
+/// // The integral of a constant is computed exactly, then rounded once.
+/// let x = [0.1, 0.2, 0.3];
+/// assert_eq!(trapezoid_xy(&x, &[1.0, 1.0, 1.0]), 0.3 - 0.1);
+/// ```
+pub fn trapezoid_xy<T: Float>(x: &[T], y: &[T]) -> T {
+    assert_eq!(x.len(), y.len(), "`x` and `y` must have the same length");
+    let mut acc = DotAccumulator::new();
+    for (x, y) in x.windows(2).zip(y.windows(2)) {
+        let (h, δh) = two_sum(x[1], -x[0]);
+        let (s, δs) = two_sum(y[0], y[1]);
+        acc += (h, s);
+        // First-order terms of the errors; the product `δh * δs` is negligible.
+        acc += (h, δs);
+        acc += (δh, s);
+    }
+    acc.total() * T::from(0.5).unwrap()
+}
+
+/// Compensated Simpson's rule for evenly spaced samples.
+///
+/// **Input:** the samples $y_0,\dots,y_{n-1}$ of a function at evenly spaced points, and the spacing $\Delta x$.
+///
+/// **Output:** the approximation $\frac{\Delta x}3\left(y_0+4y_1+2y_2+4y_3+\dots+4y_{n-2}+y_{n-1}\right)$ of the integral
+/// if $n$ is odd.
+/// If $n\geq4$ is even, Simpson's rule is applied to the first $n-1$ samples and the last interval is integrated
+/// with the quadratic through the last three samples, $\frac{\Delta x}{12}(5y_{n-1}+8y_{n-2}-y_{n-3})$;
+/// if $n=2$, the trapezoidal rule is used; if $n<2$, the result is zero.
+///
+/// The weighted sum of the samples is accumulated with [`KahanBabuskaNeumaier`] (the products by the weights being exact),
+/// and multiplied by $\Delta x/3$ as a double-word.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::simpson;
+/// // Simpson's rule is exact for cubics, here x^3 on [0, 2].
+/// let y: Vec<f64> = (0..=8).map(|i| (i as f64 * 0.25).powi(3)).collect();
+/// assert_eq!(simpson(&y, 0.25), 4.0);
+/// ```
+pub fn simpson<T: Float>(y: &[T], dx: T) -> T {
+    let n = y.len();
+    if n < 3 {
+        return trapezoid(y, dx);
+    }
+    let two = T::one() + T::one();
+    let four = two + two;
+    // Number of samples handled by Simpson's rule.
+    let m = if n % 2 == 1 { n } else { n - 1 };
+    let mut acc = KahanBabuskaNeumaier::new();
+    acc += y[0];
+    for (i, &yi) in y[1..m - 1].iter().enumerate() {
+        acc += yi * if i % 2 == 0 { four } else { two };
+    }
+    acc += y[m - 1];
+    if m < n {
+        // The quadratic through the last three samples, divided by 3 like the rest.
+        add_prod(&mut acc, T::from(1.25).unwrap(), y[n - 1]);
+        acc += y[n - 2] * two;
+        add_prod(&mut acc, T::from(-0.25).unwrap(), y[n - 3]);
+    }
+    let (h, l) = mul_dw((acc.sum, acc.comp), dx);
+    let three = two + T::one();
+    let (q, c) = div_dw((h, l), three);
+    q + c
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn trapezoid_vs_exact() {
+        use rand::prelude::*;
+        use rand_distr::LogNormal;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+        let dist = LogNormal::new(0.0, 10.0).unwrap();
+        for _ in 0..100 {
+            let y: Vec<f64> = (0..1000)
+                .map(|_| rng.sample(dist) * if rng.gen() { 1.0 } else { -1.0 })
+                .collect();
+            let mut weighted: Vec<f64> = y.clone();
+            weighted[0] *= 0.5;
+            weighted[999] *= 0.5;
+            let exact: f64 = weighted.iter().sum::<ExactSum<f64>>().total();
+            // Error bound of compensated summation.
+            let abs: f64 = y.iter().map(|y| y.abs()).sum();
+            let tol = exact.abs() * f64::EPSILON + 1000.0 * f64::EPSILON * f64::EPSILON * abs;
+            assert!((trapezoid(&y, 1.0) - exact).abs() <= tol);
+
+            // Arbitrary points with the same spacing give the same result.
+            let x: Vec<f64> = (0..1000).map(|i| 1e10 + i as f64).collect();
+            assert!((trapezoid_xy(&x, &y) - exact).abs() <= tol);
+        }
+    }
+
+    #[test]
+    fn trapezoid_subnormal() {
+        // Halving the endpoints before the sum would round them to zero.
+        assert_eq!(trapezoid(&[5e-324, 5e-324], 1.0), 5e-324);
+        assert_eq!(trapezoid(&[5e-324, 0.0, 5e-324], 2.0), 1e-323);
+    }
+
+    #[test]
+    fn trapezoid_xy_linear() {
+        use rand::prelude::*;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        // The trapezoidal rule is exact for linear functions.
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+        let mut x: Vec<f32> = (0..10_000).map(|_| rng.gen_range(1.0..2.0)).collect();
+        x.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let y: Vec<f32> = x.iter().map(|&x| 3.0 * x - 1.0).collect();
+        // Each `y` is rounded, hence compare with the exact trapezoidal sum computed in `f64`.
+        let mut exact = ExactSum::new();
+        for (x, y) in x.windows(2).zip(y.windows(2)) {
+            exact += (x[1] as f64 - x[0] as f64) * (y[0] as f64 + y[1] as f64) / 2.0;
+        }
+        let exact = exact.total() as f32;
+        assert!((trapezoid_xy(&x, &y) - exact).abs() <= exact * f32::EPSILON);
+        assert_eq!(trapezoid_xy::<f64>(&[], &[]), 0.0);
+        assert_eq!(trapezoid_xy(&[1.0], &[1.0]), 0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn trapezoid_xy_lengths() {
+        trapezoid_xy(&[1.0, 2.0], &[1.0]);
+    }
+
+    #[test]
+    fn simpson_polynomials() {
+        // Exact for cubics with an odd number of points.
+        let dx = 2f64.powi(-10);
+        let y: Vec<f64> = (0..=2048).map(|i| (i as f64 * dx).powi(3)).collect();
+        assert_eq!(simpson(&y, dx), 4.0);
+        // Exact for quadratics with an even number of points.
+        let y: Vec<f64> = (0..2048).map(|i| (i as f64 * dx).powi(2)).collect();
+        let b = 2047.0 * dx;
+        let exact = b * b * b / 3.0;
+        assert!((simpson(&y, dx) - exact).abs() <= exact * f64::EPSILON);
+        let y = [0.0, 1.0, 4.0, 9.0];
+        assert_eq!(simpson(&y, 1.0), 9.0);
+
+        assert_eq!(simpson(&[1.0, 3.0], 0.5), 1.0);
+        assert_eq!(simpson(&[1.0], 0.5), 0.0);
+    }
+
+    #[test]
+    fn simpson_vs_exact() {
+        use rand::prelude::*;
+        use rand_distr::LogNormal;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+        let dist = LogNormal::new(0.0, 10.0).unwrap();
+        for _ in 0..100 {
+            let y: Vec<f64> = (0..1001)
+                .map(|_| rng.sample(dist) * if rng.gen() { 1.0 } else { -1.0 })
+                .collect();
+            let mut sum = ExactSum::new();
+            for (i, &yi) in y.iter().enumerate() {
+                sum += yi
+                    * match i {
+                        0 | 1000 => 1.0,
+                        _ if i % 2 == 1 => 4.0,
+                        _ => 2.0,
+                    };
+            }
+            // Dividing by 3 rounds once more.
+            let exact = sum.total() / 3.0;
+            let abs: f64 = y.iter().map(|y| 4.0 * y.abs()).sum();
+            let tol = exact.abs() * f64::EPSILON + 1000.0 * f64::EPSILON * f64::EPSILON * abs;
+            assert!((simpson(&y, 1.0) - exact).abs() <= tol);
+        }
+    }
+}
@@ -0,0 +1,307 @@
+use crate::*;
+
+/// Iterator adaptor yielding the running totals of an iterator, computed with an [`Accumulator`].
+///
+/// This `struct` is created by the [`CompensatedScanExt::compensated_scan()`] and
+/// [`CompensatedScanExt::compensated_scan_with()`] methods.
+#[derive(Clone, Debug)]
+pub struct CompensatedScan<I, A> {
+    iter: I,
+    acc: A,
+}
+
+impl<I, A> Iterator for CompensatedScan<I, A>
+where
+    I: Iterator,
+    A: Accumulator + AddAssign<I::Item>,
+{
+    type Item = A::Output;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let x = self.iter.next()?;
+        self.acc += x;
+        Some(self.acc.total())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<I, A> ExactSizeIterator for CompensatedScan<I, A>
+where
+    I: ExactSizeIterator,
+    A: Accumulator + AddAssign<I::Item>,
+{
+}
+
+/// Extension trait providing compensated prefix sums for all iterators.
+///
+/// # Examples
+///
+/// ```
+/// use compensated_summation::CompensatedScanExt;
+/// let cumsum: Vec<f64> = [0.1; 10].iter().compensated_scan().collect();
+/// assert_eq!(cumsum[9], 1.0);
+/// assert_ne!([0.1; 10].iter().sum::<f64>(), 1.0);
+/// ```
+///
+/// Another accumulator can be chosen explicitly, for example [`ExactSum`] for correctly rounded prefix sums.
+///
+/// ```
+/// use compensated_summation::{CompensatedScanExt, ExactSum};
+/// let cumsum: Vec<f64> = [1e100, 1.0, -1e100, 1e-100]
+///     .into_iter()
+///     .compensated_scan_with::<ExactSum<_>>()
+///     .collect();
+/// assert_eq!(cumsum, [1e100, 1e100, 1.0, 1.0]);
+/// ```
+pub trait CompensatedScanExt: Iterator + Sized {
+    /// Yield the running totals of the items, accumulated with [`KahanBabuskaNeumaier`].
+    ///
+    /// The $n$-th item of the result is the estimated sum of the first $n$ items of the iterator.
+    fn compensated_scan<T: Float>(self) -> CompensatedScan<Self, KahanBabuskaNeumaier<T>>
+    where
+        KahanBabuskaNeumaier<T>: AddAssign<Self::Item>,
+    {
+        self.compensated_scan_with()
+    }
+
+    /// Yield the running totals of the items, accumulated with the given [`Accumulator`] type.
+    fn compensated_scan_with<A>(self) -> CompensatedScan<Self, A>
+    where
+        A: Accumulator + AddAssign<Self::Item>,
+    {
+        CompensatedScan {
+            iter: self,
+            acc: A::default(),
+        }
+    }
+}
+
+impl<I: Iterator> CompensatedScanExt for I {}
+
+/// Replace each element of a slice with the sum of the elements up to it,
+/// accumulated with [`KahanBabuskaNeumaier`].
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::cumsum_in_place;
+/// let mut x = [1.0, 1e100, 1.0, -1e100, 1.0];
+/// cumsum_in_place(&mut x);
+/// assert_eq!(x, [1.0, 1e100, 1e100, 2.0, 3.0]);
+/// ```
+pub fn cumsum_in_place<T: Float>(x: &mut [T]) {
+    cumsum_in_place_with::<KahanBabuskaNeumaier<T>, T>(x);
+}
+
+/// Replace each element of a slice with the sum of the elements up to it,
+/// accumulated with the given [`Accumulator`] type.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::{cumsum_in_place_with, KahanBabuska};
+/// let mut x = [0.1; 10];
+/// cumsum_in_place_with::<KahanBabuska<_>, _>(&mut x);
+/// assert_eq!(x[9], 1.0);
+/// ```
+pub fn cumsum_in_place_with<A, T>(x: &mut [T])
+where
+    T: Float,
+    A: Accumulator<Output = T> + AddAssign<T>,
+{
+    let mut acc = A::default();
+    for xi in x {
+        acc += *xi;
+        *xi = acc.total();
+    }
+}
+
+/// Minimum number of elements processed by each thread of [`par_cumsum_in_place`].
+const MIN_CHUNK: usize = 1 << 14;
+
+/// Replace each element of a slice with the sum of the elements up to it, using several threads.
+///
+/// The slice is split into one chunk per thread, and the scan proceeds in three passes:
+/// the total of each chunk is computed in parallel with [`KahanBabuskaNeumaier`];
+/// the carries, that is the sums of the totals of the previous chunks, are accumulated as double-words;
+/// finally, each chunk is scanned in parallel by an accumulator initialized with its carry.
+/// Hence, the result matches the one of [`cumsum_in_place`] within the error bound of the accumulator,
+/// independently of the number of threads.
+///
+/// The threads are spawned with [`std::thread::scope`].
+/// If `threads` is zero, [`std::thread::available_parallelism`] is used.
+/// Small slices are processed with fewer threads, or sequentially.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::par_cumsum_in_place;
+/// let mut x = vec![0.1; 1_000_000];
+/// par_cumsum_in_place(&mut x, 4);
+/// assert_eq!(x[9], 1.0);
+/// assert_eq!(x[999_999], 100_000.0);
+/// ```
+pub fn par_cumsum_in_place<T: Float + Send + Sync>(x: &mut [T], threads: usize) {
+    let threads = match threads {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+    let threads = threads.min(x.len().div_ceil(MIN_CHUNK));
+    if threads <= 1 {
+        cumsum_in_place(x);
+        return;
+    }
+    let chunk = x.len().div_ceil(threads);
+
+    let totals: Vec<(T, T)> = std::thread::scope(|s| {
+        let handles: Vec<_> = x
+            .chunks(chunk)
+            .map(|c| {
+                s.spawn(move || {
+                    let acc: KahanBabuskaNeumaier<T> = c.iter().sum();
+                    two_sum(acc.sum, acc.comp)
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    let mut carry = (T::zero(), T::zero());
+    let carries: Vec<(T, T)> = totals
+        .iter()
+        .map(|&(hi, lo)| {
+            let c = carry;
+            carry = add_dw(add_dw(carry, hi), lo);
+            c
+        })
+        .collect();
+
+    std::thread::scope(|s| {
+        for (c, &(hi, lo)) in x.chunks_mut(chunk).zip(&carries) {
+            s.spawn(move || {
+                let mut acc = KahanBabuskaNeumaier { sum: hi, comp: lo };
+                for xi in c {
+                    acc += *xi;
+                    *xi = acc.total();
+                }
+            });
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn scan_vs_exact() {
+        use rand::prelude::*;
+        use rand_distr::LogNormal;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+        let dist = LogNormal::new(0.0, 10.0).unwrap();
+        let values: Vec<f64> = (0..10_000)
+            .map(|_| rng.sample(dist) * if rng.gen() { 1.0 } else { -1.0 })
+            .collect();
+
+        let exact: Vec<f64> = values
+            .iter()
+            .compensated_scan_with::<ExactSum<_>>()
+            .collect();
+        let mut sum = ExactSum::new();
+        for (x, s) in values.iter().zip(&exact) {
+            sum += x;
+            assert_eq!(sum.total(), *s);
+        }
+
+        let kbn: Vec<f64> = values.iter().compensated_scan().collect();
+        let mut sum = KahanBabuskaNeumaier::new();
+        for (x, s) in values.iter().zip(&kbn) {
+            sum += x;
+            assert_eq!(sum.total(), *s);
+        }
+
+        let mut in_place = values.clone();
+        cumsum_in_place(&mut in_place);
+        assert_eq!(in_place, kbn);
+        let mut in_place = values.clone();
+        cumsum_in_place_with::<ExactSum<_>, _>(&mut in_place);
+        assert_eq!(in_place, exact);
+    }
+
+    #[test]
+    fn par_scan() {
+        use rand::prelude::*;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+        let values: Vec<f64> = (0..200_000).map(|_| rng.gen_range(0.0..1.0)).collect();
+        let exact: Vec<f64> = values
+            .iter()
+            .compensated_scan_with::<ExactSum<_>>()
+            .collect();
+        let mut seq = values.clone();
+        cumsum_in_place(&mut seq);
+
+        for threads in [0, 1, 2, 4, 7] {
+            let mut par = values.clone();
+            par_cumsum_in_place(&mut par, threads);
+            for (p, e) in par.iter().zip(&exact) {
+                assert!((p - e).abs() <= e * f64::EPSILON);
+            }
+            if threads == 1 {
+                assert_eq!(par, seq);
+            }
+        }
+    }
+
+    #[test]
+    fn par_scan_cancellation() {
+        // Large values cancelling out across chunk boundaries.
+        let mut values = vec![1.0; 100_000];
+        values[0] = 1e100;
+        values[60_000] = -1e100;
+        let mut par = values.clone();
+        par_cumsum_in_place(&mut par, 4);
+        assert_eq!(par[59_999], 1e100);
+        assert_eq!(par[60_000], 59_999.0);
+        assert_eq!(par[99_999], 99_998.0);
+
+        let mut empty: [f64; 0] = [];
+        par_cumsum_in_place(&mut empty, 4);
+        let mut small = [1.0, 2.0, 3.0];
+        par_cumsum_in_place(&mut small, 4);
+        assert_eq!(small, [1.0, 3.0, 6.0]);
+    }
+
+    #[test]
+    fn scan_drift() {
+        // The naive prefix sums of 0.1 drift away from the correctly rounded ones.
+        let values = vec![0.1f32; 100_000];
+        let exact: Vec<f32> = values
+            .iter()
+            .compensated_scan_with::<ExactSum<_>>()
+            .collect();
+        let kbn: Vec<f32> = values.iter().compensated_scan().collect();
+        let naive: Vec<f32> = values
+            .iter()
+            .scan(0.0, |s, x| {
+                *s += x;
+                Some(*s)
+            })
+            .collect();
+        // The compensation itself is summed naively, hence a few ulps of error remain.
+        for (k, e) in kbn.iter().zip(&exact) {
+            assert!((k - e).abs() <= e * 1e-6);
+        }
+        assert!((naive[99_999] - exact[99_999]).abs() > 1.0);
+
+        let scan = values.iter().compensated_scan();
+        assert_eq!(scan.len(), values.len());
+        assert_eq!(std::iter::empty::<f64>().compensated_scan().next(), None);
+    }
+}
@@ -0,0 +1,1103 @@
+use crate::*;
+
+/// This type is an accumulator for computing the mean and the variance of a sequence of floating-point numbers in one pass.
+///
+/// The generic parameter `T` should typically implement [`num_traits::float::Float`] and can usually be inferred.
+///
+/// The update is the one of [Welford (1962)](https://en.wikipedia.org/wiki/Algorithms_for_calculating_variance#Welford's_online_algorithm),
+/// which accumulates the sum of squared deviations from the running mean
+/// $M_2=\sum_i(x_i-\bar x)^2$ instead of $\sum_i x_i^2$, hence it does not suffer from cancellation
+/// when the mean is large compared with the spread.
+/// In addition, both the mean and $M_2$ are kept as compensated double-words,
+/// the deviations being computed with [`two_sum`] and their products with [`two_prod`].
+///
+/// Two accumulators can be merged with [`Moments::merge()`], using the formulas of
+/// [Chan, Golub, LeVeque (1979)](https://en.wikipedia.org/wiki/Algorithms_for_calculating_variance#Parallel_algorithm),
+/// so that the statistics of large datasets can be computed in parallel.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::Moments;
+/// let mut moments = Moments::new();
+/// for x in [1e9 + 4.0, 1e9 + 7.0, 1e9 + 13.0, 1e9 + 16.0] {
+///     moments += x;
+/// }
+/// assert_eq!(moments.count(), 4);
+/// assert_eq!(moments.mean(), 1e9 + 10.0);
+/// assert_eq!(moments.variance(), 22.5);
+/// assert_eq!(moments.sample_variance(), 30.0);
+/// ```
+///
+/// [`Moments`] also implements the [`std::iter::Sum`](#impl-Sum<V>-for-Moments<T>) trait.
+///
+/// ```
+/// # use compensated_summation::Moments;
+/// let moments = [2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0].iter().sum::<Moments<f64>>();
+/// assert_eq!(moments.mean(), 5.0);
+/// assert_eq!(moments.std_dev(), 2.0);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Moments<T> {
+    /// Number of accumulated values.
+    count: usize,
+    /// Running mean, as a double-word.
+    mean: (T, T),
+    /// Sum of squared deviations from the mean, as a double-word.
+    m2: (T, T),
+}
+
+impl<T: Float> Moments<T> {
+    /// Create a new empty accumulator.
+    pub fn new() -> Self {
+        Self {
+            count: 0,
+            mean: (T::zero(), T::zero()),
+            m2: (T::zero(), T::zero()),
+        }
+    }
+
+    /// Get the number of accumulated values.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Get the mean.
+    ///
+    /// This is NaN if the accumulator is empty.
+    pub fn mean(&self) -> T {
+        if self.count == 0 {
+            T::nan()
+        } else {
+            self.mean.0 + self.mean.1
+        }
+    }
+
+    /// Get the population variance $M_2/n$.
+    ///
+    /// This is NaN if the accumulator is empty.
+    pub fn variance(&self) -> T {
+        (self.m2.0 + self.m2.1) / T::from(self.count).unwrap()
+    }
+
+    /// Get the sample variance $M_2/(n-1)$, with Bessel's correction.
+    ///
+    /// This is NaN if fewer than two values have been accumulated.
+    pub fn sample_variance(&self) -> T {
+        if self.count < 2 {
+            T::nan()
+        } else {
+            (self.m2.0 + self.m2.1) / T::from(self.count - 1).unwrap()
+        }
+    }
+
+    /// Get the population standard deviation.
+    pub fn std_dev(&self) -> T {
+        self.variance().sqrt()
+    }
+
+    /// Get the sample standard deviation.
+    pub fn sample_std_dev(&self) -> T {
+        self.sample_variance().sqrt()
+    }
+
+    /// Merge another accumulator into this one, as if all its values had been added to this one.
+    pub fn merge(&mut self, other: &Self) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = other.clone();
+            return;
+        }
+        let na = T::from(self.count).unwrap();
+        let nb = T::from(other.count).unwrap();
+        let n = na + nb;
+        let δ = sub_dw(other.mean.0, self.mean) + other.mean.1;
+        self.mean = add_dw(self.mean, δ * (nb / n));
+        let (p, δp) = two_prod(δ, δ * (na * nb / n));
+        self.m2 = add_dw(self.m2, other.m2.0);
+        self.m2 = add_dw(self.m2, other.m2.1 + δp);
+        self.m2 = add_dw(self.m2, p);
+        self.count += other.count;
+    }
+}
+
+impl<T: Float> Default for Moments<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> AddAssign<T> for Moments<T> {
+    fn add_assign(&mut self, rhs: T) {
+        self.count += 1;
+        let n = T::from(self.count).unwrap();
+        let δ = sub_dw(rhs, self.mean);
+        self.mean = add_dw(self.mean, δ / n);
+        let δʹ = sub_dw(rhs, self.mean);
+        let (p, δp) = two_prod(δ, δʹ);
+        self.m2 = add_dw(self.m2, δp);
+        self.m2 = add_dw(self.m2, p);
+    }
+}
+
+impl<T: Float> AddAssign<&T> for Moments<T> {
+    fn add_assign(&mut self, rhs: &T) {
+        *self += *rhs;
+    }
+}
+
+impl<T: Float, V> Add<V> for Moments<T>
+where
+    Self: AddAssign<V>,
+{
+    type Output = Self;
+    fn add(mut self, rhs: V) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl<T: Float, V> Sum<V> for Moments<T>
+where
+    Self: AddAssign<V>,
+{
+    fn sum<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = V>,
+    {
+        let mut moments = Moments::new();
+        for x in iter {
+            moments += x;
+        }
+        moments
+    }
+}
+
+/// This type is an accumulator for computing the mean, variance, skewness and kurtosis of a sequence of floating-point numbers in one pass.
+///
+/// The generic parameter `T` should typically implement [`num_traits::float::Float`] and can usually be inferred.
+///
+/// Like [`Moments`], it accumulates the sums of powers of deviations from the running mean
+/// $M_k=\sum_i(x_i-\bar x)^k$ for $k=2,3,4$, using the one-pass update and merge formulas of
+/// Pébay, *Formulas for robust, one-pass parallel computation of covariances and arbitrary-order statistical moments* (2008).
+/// The mean and the sums $M_k$ are kept as compensated double-words and the deviations are computed with [`two_sum`],
+/// hence the results stay accurate even when the mean is large compared with the spread.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::HigherMoments;
+/// let moments: HigherMoments<f64> = [1e9 + 1.0, 1e9 + 2.0, 1e9 + 3.0, 1e9 + 10.0].iter().sum();
+/// assert_eq!(moments.mean(), 1e9 + 4.0);
+/// assert_eq!(moments.variance(), 12.5);
+/// // The deviations from the mean are -3, -2, -1 and 6.
+/// let m3 = (-27.0 - 8.0 - 1.0 + 216.0) / 4.0;
+/// let m4 = (81.0 + 16.0 + 1.0 + 1296.0) / 4.0;
+/// assert!((moments.skewness() - m3 / 12.5f64.powf(1.5)).abs() < 1e-15);
+/// assert!((moments.excess_kurtosis() - (m4 / 12.5 / 12.5 - 3.0)).abs() < 1e-15);
+/// ```
+///
+/// Accumulators computed on different parts of a dataset can be merged.
+///
+/// ```
+/// # use compensated_summation::HigherMoments;
+/// let mut a: HigherMoments<f64> = [1.0, 2.0].iter().sum();
+/// a.merge(&[3.0, 10.0].iter().sum());
+/// assert_eq!(a.skewness(), [1.0, 2.0, 3.0, 10.0].iter().sum::<HigherMoments<f64>>().skewness());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct HigherMoments<T> {
+    /// Number of accumulated values.
+    count: usize,
+    /// Running mean, as a double-word.
+    mean: (T, T),
+    /// Sum of squared deviations from the mean, as a double-word.
+    m2: (T, T),
+    /// Sum of cubed deviations from the mean, as a double-word.
+    m3: (T, T),
+    /// Sum of fourth powers of deviations from the mean, as a double-word.
+    m4: (T, T),
+}
+
+impl<T: Float> HigherMoments<T> {
+    /// Create a new empty accumulator.
+    pub fn new() -> Self {
+        let zero = (T::zero(), T::zero());
+        Self {
+            count: 0,
+            mean: zero,
+            m2: zero,
+            m3: zero,
+            m4: zero,
+        }
+    }
+
+    /// Get the number of accumulated values.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Get the mean.
+    ///
+    /// This is NaN if the accumulator is empty.
+    pub fn mean(&self) -> T {
+        if self.count == 0 {
+            T::nan()
+        } else {
+            self.mean.0 + self.mean.1
+        }
+    }
+
+    /// Get the population variance $M_2/n$.
+    ///
+    /// This is NaN if the accumulator is empty.
+    pub fn variance(&self) -> T {
+        (self.m2.0 + self.m2.1) / T::from(self.count).unwrap()
+    }
+
+    /// Get the sample variance $M_2/(n-1)$, with Bessel's correction.
+    ///
+    /// This is NaN if fewer than two values have been accumulated.
+    pub fn sample_variance(&self) -> T {
+        if self.count < 2 {
+            T::nan()
+        } else {
+            (self.m2.0 + self.m2.1) / T::from(self.count - 1).unwrap()
+        }
+    }
+
+    /// Get the population standard deviation.
+    pub fn std_dev(&self) -> T {
+        self.variance().sqrt()
+    }
+
+    /// Get the sample standard deviation.
+    pub fn sample_std_dev(&self) -> T {
+        self.sample_variance().sqrt()
+    }
+
+    /// Get the population skewness $g_1=\sqrt n M_3/M_2^{3/2}$.
+    ///
+    /// This is NaN if the variance is zero.
+    pub fn skewness(&self) -> T {
+        let n = T::from(self.count).unwrap();
+        let m2 = self.m2.0 + self.m2.1;
+        let m3 = self.m3.0 + self.m3.1;
+        n.sqrt() * m3 / (m2 * m2.sqrt())
+    }
+
+    /// Get the population excess kurtosis $g_2=nM_4/M_2^2-3$.
+    ///
+    /// This is NaN if the variance is zero.
+    pub fn excess_kurtosis(&self) -> T {
+        let n = T::from(self.count).unwrap();
+        let m2 = self.m2.0 + self.m2.1;
+        let m4 = self.m4.0 + self.m4.1;
+        n * m4 / (m2 * m2) - T::from(3).unwrap()
+    }
+
+    /// Merge another accumulator into this one, as if all its values had been added to this one.
+    pub fn merge(&mut self, other: &Self) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = other.clone();
+            return;
+        }
+        let c = |k: i32| T::from(k).unwrap();
+        let na = T::from(self.count).unwrap();
+        let nb = T::from(other.count).unwrap();
+        let n = na + nb;
+        let (m2a, m3a) = (self.m2.0 + self.m2.1, self.m3.0 + self.m3.1);
+        let (m2b, m3b) = (other.m2.0 + other.m2.1, other.m3.0 + other.m3.1);
+        let δ = sub_dw(other.mean.0, self.mean) + other.mean.1;
+        let δ_n = δ / n;
+        let δ2 = δ * δ_n;
+        self.mean = add_dw(self.mean, δ_n * nb);
+        // The updates of the higher sums depend on the old values of the lower ones.
+        let t4 = δ2 * δ_n * δ_n * na * nb * (na * na - na * nb + nb * nb)
+            + c(6) * δ_n * δ_n * (na * na * m2b + nb * nb * m2a)
+            + c(4) * δ_n * (na * m3b - nb * m3a);
+        let t3 = δ2 * δ_n * na * nb * (na - nb) + c(3) * δ_n * (na * m2b - nb * m2a);
+        let t2 = δ2 * na * nb;
+        self.m4 = add_dw(add_dw(add_dw(self.m4, other.m4.0), other.m4.1), t4);
+        self.m3 = add_dw(add_dw(add_dw(self.m3, other.m3.0), other.m3.1), t3);
+        self.m2 = add_dw(add_dw(add_dw(self.m2, other.m2.0), other.m2.1), t2);
+        self.count += other.count;
+    }
+}
+
+impl<T: Float> Default for HigherMoments<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> AddAssign<T> for HigherMoments<T> {
+    fn add_assign(&mut self, rhs: T) {
+        let c = |k: i32| T::from(k).unwrap();
+        let n1 = T::from(self.count).unwrap();
+        self.count += 1;
+        let n = n1 + T::one();
+        let m2 = self.m2.0 + self.m2.1;
+        let m3 = self.m3.0 + self.m3.1;
+        let δ = sub_dw(rhs, self.mean);
+        let δ_n = δ / n;
+        let δ_n2 = δ_n * δ_n;
+        let (p, δp) = two_prod(δ, δ_n * n1);
+        let term = p + δp;
+        self.mean = add_dw(self.mean, δ_n);
+        let t4 = term * δ_n2 * (n * n - c(3) * n + c(3)) + c(6) * δ_n2 * m2 - c(4) * δ_n * m3;
+        let t3 = term * δ_n * (n - c(2)) - c(3) * δ_n * m2;
+        self.m4 = add_dw(self.m4, t4);
+        self.m3 = add_dw(self.m3, t3);
+        self.m2 = add_dw(add_dw(self.m2, δp), p);
+    }
+}
+
+impl<T: Float> AddAssign<&T> for HigherMoments<T> {
+    fn add_assign(&mut self, rhs: &T) {
+        *self += *rhs;
+    }
+}
+
+impl<T: Float, V> Add<V> for HigherMoments<T>
+where
+    Self: AddAssign<V>,
+{
+    type Output = Self;
+    fn add(mut self, rhs: V) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl<T: Float, V> Sum<V> for HigherMoments<T>
+where
+    Self: AddAssign<V>,
+{
+    fn sum<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = V>,
+    {
+        let mut moments = HigherMoments::new();
+        for x in iter {
+            moments += x;
+        }
+        moments
+    }
+}
+
+/// This type is an accumulator for computing the covariance, the correlation and the simple linear regression
+/// of a sequence of pairs of floating-point numbers in one pass.
+///
+/// The generic parameter `T` should typically implement [`num_traits::float::Float`] and can usually be inferred.
+///
+/// Like [`Moments`], it uses a compensated Welford update: the means are kept as double-words,
+/// together with the sums of squared deviations $M_{2,x}$ and $M_{2,y}$ and the co-moment
+/// $C=\sum_i(x_i-\bar x)(y_i-\bar y)$, instead of the raw sums $\sum_i x_i y_i$, $\sum_i x_i$ and $\sum_i y_i$,
+/// hence the results do not suffer from cancellation when the means are large compared with the spreads.
+/// Accumulators can be merged with [`Bivariate::merge()`].
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::Bivariate;
+/// let mut reg = Bivariate::new();
+/// for i in 0..10 {
+///     let x = 1e9 + i as f64;
+///     reg += (x, 3.0 * x + 2.0);
+/// }
+/// assert_eq!(reg.slope(), 3.0);
+/// assert_eq!(reg.intercept(), 2.0);
+/// assert_eq!(reg.correlation(), 1.0);
+/// ```
+///
+/// [`Bivariate`] also implements the [`std::iter::Sum`](#impl-Sum<V>-for-Bivariate<T>) trait,
+/// hence two slices can be zipped and summed.
+///
+/// ```
+/// # use compensated_summation::Bivariate;
+/// let x = [1.0, 2.0, 3.0, 4.0];
+/// let y = [2.0, 1.0, 4.0, 3.0];
+/// let reg: Bivariate<f64> = x.iter().zip(&y).sum();
+/// assert_eq!(reg.covariance(), 0.75);
+/// assert!((reg.correlation() - 0.6).abs() < 1e-15);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bivariate<T> {
+    /// Number of accumulated pairs.
+    count: usize,
+    /// Running mean of the first components, as a double-word.
+    mean_x: (T, T),
+    /// Running mean of the second components, as a double-word.
+    mean_y: (T, T),
+    /// Sum of squared deviations of the first components, as a double-word.
+    m2_x: (T, T),
+    /// Sum of squared deviations of the second components, as a double-word.
+    m2_y: (T, T),
+    /// Sum of products of the deviations, as a double-word.
+    c_xy: (T, T),
+}
+
+impl<T: Float> Bivariate<T> {
+    /// Create a new empty accumulator.
+    pub fn new() -> Self {
+        let zero = (T::zero(), T::zero());
+        Self {
+            count: 0,
+            mean_x: zero,
+            mean_y: zero,
+            m2_x: zero,
+            m2_y: zero,
+            c_xy: zero,
+        }
+    }
+
+    /// Get the number of accumulated pairs.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Get the mean of the first components.
+    ///
+    /// This is NaN if the accumulator is empty.
+    pub fn mean_x(&self) -> T {
+        if self.count == 0 {
+            T::nan()
+        } else {
+            self.mean_x.0 + self.mean_x.1
+        }
+    }
+
+    /// Get the mean of the second components.
+    ///
+    /// This is NaN if the accumulator is empty.
+    pub fn mean_y(&self) -> T {
+        if self.count == 0 {
+            T::nan()
+        } else {
+            self.mean_y.0 + self.mean_y.1
+        }
+    }
+
+    /// Get the population variance of the first components.
+    pub fn variance_x(&self) -> T {
+        (self.m2_x.0 + self.m2_x.1) / T::from(self.count).unwrap()
+    }
+
+    /// Get the population variance of the second components.
+    pub fn variance_y(&self) -> T {
+        (self.m2_y.0 + self.m2_y.1) / T::from(self.count).unwrap()
+    }
+
+    /// Get the population covariance $C/n$.
+    ///
+    /// This is NaN if the accumulator is empty.
+    pub fn covariance(&self) -> T {
+        (self.c_xy.0 + self.c_xy.1) / T::from(self.count).unwrap()
+    }
+
+    /// Get the sample covariance $C/(n-1)$, with Bessel's correction.
+    ///
+    /// This is NaN if fewer than two pairs have been accumulated.
+    pub fn sample_covariance(&self) -> T {
+        if self.count < 2 {
+            T::nan()
+        } else {
+            (self.c_xy.0 + self.c_xy.1) / T::from(self.count - 1).unwrap()
+        }
+    }
+
+    /// Get the Pearson correlation coefficient $C/\sqrt{M_{2,x}M_{2,y}}$.
+    ///
+    /// This is NaN if either variance is zero.
+    pub fn correlation(&self) -> T {
+        let m2_x = self.m2_x.0 + self.m2_x.1;
+        let m2_y = self.m2_y.0 + self.m2_y.1;
+        (self.c_xy.0 + self.c_xy.1) / (m2_x.sqrt() * m2_y.sqrt())
+    }
+
+    /// Get the slope $C/M_{2,x}$ of the least-squares regression line of $y$ on $x$.
+    ///
+    /// This is NaN if the variance of the first components is zero.
+    pub fn slope(&self) -> T {
+        (self.c_xy.0 + self.c_xy.1) / (self.m2_x.0 + self.m2_x.1)
+    }
+
+    /// Get the intercept $\bar y-b\bar x$ of the least-squares regression line of $y$ on $x$, where $b$ is the slope.
+    pub fn intercept(&self) -> T {
+        let b = self.slope();
+        let (p, δp) = two_prod(b, self.mean_x.0);
+        let (d, δd) = two_sum(self.mean_y.0, -p);
+        d + ((δd - δp) + (self.mean_y.1 - b * self.mean_x.1))
+    }
+
+    /// Get the coefficient of determination $R^2$ of the least-squares regression line,
+    /// which is the square of the correlation coefficient.
+    pub fn r_squared(&self) -> T {
+        let r = self.correlation();
+        r * r
+    }
+
+    /// Merge another accumulator into this one, as if all its pairs had been added to this one.
+    pub fn merge(&mut self, other: &Self) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = other.clone();
+            return;
+        }
+        let na = T::from(self.count).unwrap();
+        let nb = T::from(other.count).unwrap();
+        let n = na + nb;
+        let δx = sub_dw(other.mean_x.0, self.mean_x) + other.mean_x.1;
+        let δy = sub_dw(other.mean_y.0, self.mean_y) + other.mean_y.1;
+        self.mean_x = add_dw(self.mean_x, δx * (nb / n));
+        self.mean_y = add_dw(self.mean_y, δy * (nb / n));
+        let f = na * nb / n;
+        let merge = |a: (T, T), b: (T, T), u: T, v: T| {
+            let (p, δp) = two_prod(u, v * f);
+            add_dw(add_dw(add_dw(a, b.0), b.1 + δp), p)
+        };
+        self.m2_x = merge(self.m2_x, other.m2_x, δx, δx);
+        self.m2_y = merge(self.m2_y, other.m2_y, δy, δy);
+        self.c_xy = merge(self.c_xy, other.c_xy, δx, δy);
+        self.count += other.count;
+    }
+}
+
+impl<T: Float> Default for Bivariate<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> AddAssign<(T, T)> for Bivariate<T> {
+    fn add_assign(&mut self, (x, y): (T, T)) {
+        self.count += 1;
+        let n = T::from(self.count).unwrap();
+        let δx = sub_dw(x, self.mean_x);
+        let δy = sub_dw(y, self.mean_y);
+        self.mean_x = add_dw(self.mean_x, δx / n);
+        self.mean_y = add_dw(self.mean_y, δy / n);
+        let δxʹ = sub_dw(x, self.mean_x);
+        let δyʹ = sub_dw(y, self.mean_y);
+        let update = |m: (T, T), u: T, v: T| {
+            let (p, δp) = two_prod(u, v);
+            add_dw(add_dw(m, δp), p)
+        };
+        self.m2_x = update(self.m2_x, δx, δxʹ);
+        self.m2_y = update(self.m2_y, δy, δyʹ);
+        self.c_xy = update(self.c_xy, δx, δyʹ);
+    }
+}
+
+impl<T: Float> AddAssign<(&T, &T)> for Bivariate<T> {
+    fn add_assign(&mut self, (x, y): (&T, &T)) {
+        *self += (*x, *y);
+    }
+}
+
+impl<T: Float, V> Add<V> for Bivariate<T>
+where
+    Self: AddAssign<V>,
+{
+    type Output = Self;
+    fn add(mut self, rhs: V) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl<T: Float, V> Sum<V> for Bivariate<T>
+where
+    Self: AddAssign<V>,
+{
+    fn sum<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = V>,
+    {
+        let mut reg = Bivariate::new();
+        for p in iter {
+            reg += p;
+        }
+        reg
+    }
+}
+
+/// This type is an accumulator for computing weighted sums, means and variances of a sequence of
+/// `(value, weight)` pairs of floating-point numbers in one pass.
+///
+/// The generic parameter `T` should typically implement [`num_traits::float::Float`] and can usually be inferred.
+///
+/// Each product $w_ix_i$ is computed exactly with [`two_prod`] and, like the sum of the weights $W=\sum_iw_i$
+/// and the sum of the squared weights, it is accumulated in a compensated double-word,
+/// hence the rounding error of the products does not get lost before reaching the sum.
+/// The weighted variance is computed with the weighted variant of the Welford update of
+/// West, *Updating mean and variance estimates: an improved method* (1979),
+/// with the mean and the sum of weighted squared deviations $M_2=\sum_iw_i(x_i-\bar x)^2$ kept as double-words.
+/// Accumulators can be merged with [`WeightedSum::merge()`].
+///
+/// The weights are expected to be nonnegative; pairs with a zero weight do not contribute to any statistic.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::WeightedSum;
+/// let mut sum = WeightedSum::new();
+/// sum += (1e8 + 1.0, 1e8 - 1.0);
+/// sum += (-1e8, 1e8);
+/// // Multiplying first would round 1e16 - 1 to an even number.
+/// assert_eq!(sum.total(), -1.0);
+/// assert_eq!(sum.weight(), 2e8 - 1.0);
+/// ```
+///
+/// [`WeightedSum`] also implements the [`std::iter::Sum`](#impl-Sum<V>-for-WeightedSum<T>) trait,
+/// hence slices of values and weights can be zipped and summed.
+///
+/// ```
+/// # use compensated_summation::WeightedSum;
+/// let values = [1.0, 2.0, 4.0];
+/// let weights = [1.0, 2.0, 1.0];
+/// let sum: WeightedSum<f64> = values.iter().zip(&weights).sum();
+/// assert_eq!(sum.weighted_mean(), 2.25);
+/// assert_eq!(sum.variance(), 1.1875);
+/// assert_eq!(sum.frequency_variance(), 1.1875 * 4.0 / 3.0);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeightedSum<T> {
+    /// Number of accumulated pairs.
+    count: usize,
+    /// Sum of the products of the values by their weights, as a double-word.
+    sum: (T, T),
+    /// Sum of the weights, as a double-word.
+    weight: (T, T),
+    /// Sum of the squared weights, as a double-word.
+    weight2: (T, T),
+    /// Running weighted mean, as a double-word.
+    mean: (T, T),
+    /// Sum of weighted squared deviations from the mean, as a double-word.
+    m2: (T, T),
+}
+
+impl<T: Float> WeightedSum<T> {
+    /// Create a new empty accumulator.
+    pub fn new() -> Self {
+        let zero = (T::zero(), T::zero());
+        Self {
+            count: 0,
+            sum: zero,
+            weight: zero,
+            weight2: zero,
+            mean: zero,
+            m2: zero,
+        }
+    }
+
+    /// Get the number of accumulated pairs.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Get the estimated weighted sum $\sum_iw_ix_i$.
+    pub fn total(&self) -> T {
+        self.sum.0 + self.sum.1
+    }
+
+    /// Get the estimated sum of the weights $W=\sum_iw_i$.
+    pub fn weight(&self) -> T {
+        self.weight.0 + self.weight.1
+    }
+
+    /// Get the weighted mean $\sum_iw_ix_i/W$.
+    ///
+    /// This is NaN if the sum of the weights is zero.
+    pub fn weighted_mean(&self) -> T {
+        self.total() / self.weight()
+    }
+
+    /// Get the weighted population variance $M_2/W$.
+    ///
+    /// This is NaN if the sum of the weights is zero.
+    pub fn variance(&self) -> T {
+        (self.m2.0 + self.m2.1) / self.weight()
+    }
+
+    /// Get the unbiased weighted variance $M_2/(W-1)$ for frequency weights,
+    /// where each weight is the number of occurrences of the value.
+    pub fn frequency_variance(&self) -> T {
+        (self.m2.0 + self.m2.1) / (self.weight() - T::one())
+    }
+
+    /// Get the unbiased weighted variance $M_2/(W-\sum_iw_i^2/W)$ for reliability weights,
+    /// where each weight is proportional to the reliability of the value.
+    pub fn reliability_variance(&self) -> T {
+        let w = self.weight();
+        (self.m2.0 + self.m2.1) / (w - (self.weight2.0 + self.weight2.1) / w)
+    }
+
+    /// Merge another accumulator into this one, as if all its pairs had been added to this one.
+    pub fn merge(&mut self, other: &Self) {
+        let wa = self.weight();
+        let wb = other.weight();
+        self.count += other.count;
+        self.sum = add_dw(add_dw(self.sum, other.sum.0), other.sum.1);
+        self.weight = add_dw(add_dw(self.weight, other.weight.0), other.weight.1);
+        self.weight2 = add_dw(add_dw(self.weight2, other.weight2.0), other.weight2.1);
+        if wb == T::zero() {
+            return;
+        }
+        if wa == T::zero() {
+            self.mean = other.mean;
+            self.m2 = other.m2;
+            return;
+        }
+        let w = self.weight();
+        let δ = sub_dw(other.mean.0, self.mean) + other.mean.1;
+        self.mean = add_dw(self.mean, δ * (wb / w));
+        let (p, δp) = two_prod(δ, δ * (wa * wb / w));
+        self.m2 = add_dw(self.m2, other.m2.0);
+        self.m2 = add_dw(self.m2, other.m2.1 + δp);
+        self.m2 = add_dw(self.m2, p);
+    }
+}
+
+impl<T: Float> Default for WeightedSum<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Float> Accumulator for WeightedSum<T> {
+    type Output = T;
+    fn total(&self) -> T {
+        self.total()
+    }
+    fn merge(&mut self, other: &Self) {
+        self.merge(other)
+    }
+}
+
+impl<T: Float> AddAssign<(T, T)> for WeightedSum<T> {
+    fn add_assign(&mut self, (x, w): (T, T)) {
+        self.count += 1;
+        let (p, δp) = two_prod(w, x);
+        self.sum = add_dw(add_dw(self.sum, δp), p);
+        let (p, δp) = two_prod(w, w);
+        self.weight2 = add_dw(add_dw(self.weight2, δp), p);
+        if w == T::zero() {
+            return;
+        }
+        self.weight = add_dw(self.weight, w);
+        let δ = sub_dw(x, self.mean);
+        self.mean = add_dw(self.mean, δ * (w / self.weight()));
+        let δʹ = sub_dw(x, self.mean);
+        let (p, δp) = two_prod(w * δ, δʹ);
+        self.m2 = add_dw(self.m2, δp);
+        self.m2 = add_dw(self.m2, p);
+    }
+}
+
+impl<T: Float> AddAssign<(&T, &T)> for WeightedSum<T> {
+    fn add_assign(&mut self, (x, w): (&T, &T)) {
+        *self += (*x, *w);
+    }
+}
+
+impl<T: Float, V> Add<V> for WeightedSum<T>
+where
+    Self: AddAssign<V>,
+{
+    type Output = Self;
+    fn add(mut self, rhs: V) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+impl<T: Float, V> Sum<V> for WeightedSum<T>
+where
+    Self: AddAssign<V>,
+{
+    fn sum<I>(iter: I) -> Self
+    where
+        I: Iterator<Item = V>,
+    {
+        let mut sum = WeightedSum::new();
+        for p in iter {
+            sum += p;
+        }
+        sum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    /// Two-pass reference for the mean and the central moments $M_k/n$ for $k=2,3,4$,
+    /// with exact sums and the mean as a double-word.
+    fn reference(values: &[f64]) -> (f64, [f64; 3]) {
+        let n = values.len() as f64;
+        let mut sum: ExactSum<f64> = values.iter().sum();
+        let mean = sum.total() / n;
+        let (p, e) = two_prod(n, mean);
+        sum -= p;
+        sum -= e;
+        let mean_lo = sum.total() / n;
+        let mut m = [ExactSum::new(), ExactSum::new(), ExactSum::new()];
+        for &x in values {
+            let d = (x - mean) - mean_lo;
+            let (d2, e2) = two_prod(d, d);
+            m[0] += d2;
+            m[0] += e2;
+            m[1] += d2 * d;
+            m[2] += d2 * d2;
+        }
+        (mean, m.map(|m| m.total() / n))
+    }
+
+    fn sample(seed: u64, n: usize, mean: f64, std_dev: f64) -> Vec<f64> {
+        use rand::prelude::*;
+        use rand_distr::Normal;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        Xoshiro256PlusPlus::seed_from_u64(seed)
+            .sample_iter(Normal::new(mean, std_dev).unwrap())
+            .take(n)
+            .collect()
+    }
+
+    #[test]
+    fn moments_large_mean() {
+        for seed in 0..20 {
+            let values = sample(seed, 10_000, 1e9, 1e-3);
+            let (mean, [var, _, _]) = reference(&values);
+            let moments: Moments<f64> = values.iter().sum();
+            assert_eq!(moments.count(), values.len());
+            assert!((moments.mean() - mean).abs() <= mean * f64::EPSILON);
+            assert!((moments.variance() - var).abs() <= var * 1e-12);
+
+            // The textbook formula is useless here.
+            let n = values.len() as f64;
+            let sum = values.iter().sum::<KahanBabuskaNeumaier<f64>>().total();
+            let sum2 = values
+                .iter()
+                .map(|x| x * x)
+                .sum::<KahanBabuskaNeumaier<f64>>()
+                .total();
+            assert!((sum2 / n - (sum / n) * (sum / n) - var).abs() >= var / 2.0);
+        }
+    }
+
+    #[test]
+    fn moments_merge() {
+        let values = sample(42, 1000, 1e6, 1.0);
+        let whole: Moments<f64> = values.iter().sum();
+        for size in [1, 7, 100, 999] {
+            let mut merged = Moments::new();
+            for chunk in values.chunks(size) {
+                merged.merge(&chunk.iter().sum());
+            }
+            assert_eq!(merged.count(), whole.count());
+            assert!((merged.mean() - whole.mean()).abs() <= whole.mean() * f64::EPSILON);
+            assert!((merged.variance() - whole.variance()).abs() <= whole.variance() * 1e-12);
+        }
+    }
+
+    #[test]
+    fn higher_moments_large_mean() {
+        use rand::prelude::*;
+        use rand_distr::Exp1;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        for seed in 0..20 {
+            // Shifted exponential distribution, with skewness 2 and excess kurtosis 6.
+            let values: Vec<f64> = Xoshiro256PlusPlus::seed_from_u64(seed)
+                .sample_iter(Exp1)
+                .take(10_000)
+                .map(|x: f64| 1e9 + x)
+                .collect();
+            let (mean, [m2, m3, m4]) = reference(&values);
+            let skewness = m3 / m2.powf(1.5);
+            let kurtosis = m4 / (m2 * m2) - 3.0;
+            assert!((skewness - 2.0).abs() < 0.5);
+            assert!((kurtosis - 6.0).abs() < 3.0);
+
+            let moments: HigherMoments<f64> = values.iter().sum();
+            assert_eq!(moments.count(), values.len());
+            assert!((moments.mean() - mean).abs() <= mean * f64::EPSILON);
+            assert!((moments.variance() - m2).abs() <= m2 * 1e-12);
+            assert!((moments.skewness() - skewness).abs() <= 1e-10);
+            assert!((moments.excess_kurtosis() - kurtosis).abs() <= 1e-10);
+
+            let mut merged = HigherMoments::new();
+            for chunk in values.chunks(333) {
+                merged.merge(&chunk.iter().sum());
+            }
+            assert_eq!(merged.count(), values.len());
+            assert!((merged.mean() - mean).abs() <= mean * f64::EPSILON);
+            assert!((merged.variance() - m2).abs() <= m2 * 1e-12);
+            assert!((merged.skewness() - skewness).abs() <= 1e-10);
+            assert!((merged.excess_kurtosis() - kurtosis).abs() <= 1e-10);
+        }
+    }
+
+    #[test]
+    fn higher_moments_vs_moments() {
+        let values = sample(7, 1000, 10.0, 3.0);
+        let moments: Moments<f64> = values.iter().sum();
+        let higher: HigherMoments<f64> = values.iter().sum();
+        assert_eq!(higher.mean(), moments.mean());
+        assert!((higher.variance() - moments.variance()).abs() <= moments.variance() * 1e-14);
+        let sd = moments.sample_std_dev();
+        assert!((higher.sample_std_dev() - sd).abs() <= sd * 1e-14);
+        assert!([1.0]
+            .iter()
+            .sum::<HigherMoments<f64>>()
+            .sample_std_dev()
+            .is_nan());
+        assert!(HigherMoments::<f64>::new().skewness().is_nan());
+        assert!([1.0, 1.0]
+            .iter()
+            .sum::<HigherMoments<f64>>()
+            .excess_kurtosis()
+            .is_nan());
+    }
+
+    #[test]
+    fn bivariate_large_mean() {
+        for seed in 0..20 {
+            let x = sample(seed, 10_000, 1e9, 1.0);
+            let noise = sample(seed + 100, 10_000, 0.0, 1e-3);
+            let y: Vec<f64> = x
+                .iter()
+                .zip(&noise)
+                .map(|(x, e)| 1e9 - 0.5 * x + e)
+                .collect();
+
+            let (_, [var_x, _, _]) = reference(&x);
+            let (_, [var_y, _, _]) = reference(&y);
+            // Var(x + y) = Var(x) + Var(y) + 2 Cov(x, y)
+            let s: Vec<f64> = x.iter().zip(&y).map(|(x, y)| x + y).collect();
+            let (_, [var_s, _, _]) = reference(&s);
+            let cov = (var_s - var_x - var_y) / 2.0;
+
+            let reg: Bivariate<f64> = x.iter().zip(&y).sum();
+            assert_eq!(reg.count(), x.len());
+            assert!((reg.variance_x() - var_x).abs() <= var_x * 1e-12);
+            assert!((reg.variance_y() - var_y).abs() <= var_y * 1e-12);
+            assert!((reg.covariance() - cov).abs() <= cov.abs() * 1e-6);
+            assert!((reg.slope() + 0.5).abs() < 1e-4);
+            assert!((reg.correlation() + 1.0).abs() < 1e-4);
+            assert!(reg.r_squared() <= 1.0);
+
+            let mut merged = Bivariate::new();
+            for (x, y) in x.chunks(1000).zip(y.chunks(1000)) {
+                merged.merge(&x.iter().zip(y).sum());
+            }
+            assert_eq!(merged.count(), reg.count());
+            assert!((merged.covariance() - reg.covariance()).abs() <= cov.abs() * 1e-12);
+            assert!((merged.slope() - reg.slope()).abs() <= 1e-12);
+            assert!((merged.intercept() - reg.intercept()).abs() <= 1e-3);
+        }
+    }
+
+    #[test]
+    fn bivariate_vs_moments() {
+        let x = sample(1, 1000, 5.0, 2.0);
+        let y = sample(2, 1000, -3.0, 1.0);
+        let reg: Bivariate<f64> = x.iter().zip(&y).sum();
+        let mx: Moments<f64> = x.iter().sum();
+        let my: Moments<f64> = y.iter().sum();
+        assert_eq!(reg.mean_x(), mx.mean());
+        assert_eq!(reg.mean_y(), my.mean());
+        assert_eq!(reg.variance_x(), mx.variance());
+        assert_eq!(reg.variance_y(), my.variance());
+        let diag: Bivariate<f64> = x.iter().zip(&x).sum();
+        assert!((diag.covariance() - mx.variance()).abs() <= mx.variance() * 1e-15);
+        assert!(Bivariate::<f64>::new().covariance().is_nan());
+        assert!((Bivariate::new() + (1.0, 2.0)).sample_covariance().is_nan());
+    }
+
+    #[test]
+    fn weighted_sum_frequency() {
+        use rand::prelude::*;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        // Integer weights are equivalent to repeating the values.
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(3);
+        for seed in 0..20 {
+            let values = sample(seed, 1000, 1e9, 1e-3);
+            let weights: Vec<f64> = (0..values.len())
+                .map(|_| rng.gen_range(0..5) as f64)
+                .collect();
+            let expanded: Vec<f64> = values
+                .iter()
+                .zip(&weights)
+                .flat_map(|(&x, &w)| std::iter::repeat_n(x, w as usize))
+                .collect();
+            let (mean, [var, _, _]) = reference(&expanded);
+            let n = expanded.len() as f64;
+
+            let sum: WeightedSum<f64> = values.iter().zip(&weights).sum();
+            assert_eq!(sum.count(), values.len());
+            assert_eq!(sum.weight(), n);
+            assert_eq!(sum.total(), dot_exact(&values, &weights));
+            assert!((sum.weighted_mean() - mean).abs() <= mean * f64::EPSILON);
+            assert!((sum.variance() - var).abs() <= var * 1e-12);
+            let sample_var = var * n / (n - 1.0);
+            assert!((sum.frequency_variance() - sample_var).abs() <= var * 1e-12);
+
+            let mut merged = WeightedSum::new();
+            for (x, w) in values.chunks(100).zip(weights.chunks(100)) {
+                merged.merge(&x.iter().zip(w).sum());
+            }
+            assert_eq!(merged.count(), sum.count());
+            assert_eq!(merged.total(), sum.total());
+            assert_eq!(merged.weight(), sum.weight());
+            assert!((merged.variance() - var).abs() <= var * 1e-12);
+        }
+    }
+
+    #[test]
+    fn weighted_sum_reliability() {
+        // With unit weights, the reliability and frequency variances are the sample variance.
+        let values = sample(5, 1000, 3.0, 2.0);
+        let moments: Moments<f64> = values.iter().sum();
+        let sum: WeightedSum<f64> = values.iter().map(|&x| (x, 1.0)).sum();
+        let var = moments.sample_variance();
+        assert!((sum.frequency_variance() - var).abs() <= var * 1e-15);
+        assert!((sum.reliability_variance() - var).abs() <= var * 1e-15);
+        // The reliability variance does not depend on the scale of the weights.
+        let scaled: WeightedSum<f64> = values.iter().map(|&x| (x, 0.125)).sum();
+        assert!((scaled.reliability_variance() - var).abs() <= var * 1e-15);
+
+        let empty = WeightedSum::<f64>::new() + (1.0, 0.0);
+        assert!(empty.weighted_mean().is_nan());
+        assert!(empty.variance().is_nan());
+        let mut merged = empty.clone();
+        merged.merge(&sum);
+        assert_eq!(merged.variance(), sum.variance());
+    }
+
+    #[test]
+    fn moments_small() {
+        let empty = Moments::<f64>::new();
+        assert!(empty.mean().is_nan());
+        assert!(empty.variance().is_nan());
+        let one: Moments<f64> = [3.0].iter().sum();
+        assert_eq!(one.mean(), 3.0);
+        assert_eq!(one.variance(), 0.0);
+        assert!(one.sample_variance().is_nan());
+        let mut merged = empty.clone();
+        merged.merge(&one);
+        merged.merge(&empty);
+        assert_eq!(merged, one);
+    }
+}
@@ -0,0 +1,330 @@
+use crate::*;
+use std::collections::VecDeque;
+
+/// This type is an accumulator for computing the sum, mean and variance of the last values of a stream,
+/// in a sliding window of fixed capacity.
+///
+/// The generic parameter `T` should typically implement [`num_traits::float::Float`] and can usually be inferred.
+///
+/// The sum of the values in the window, and the sum of their squares (each computed exactly with [`two_prod`]),
+/// are kept as [`ExactSum`] expansions: the newest value is added and the evicted one is subtracted exactly,
+/// hence the running total never drifts from the sum of the window, however many values pass through it.
+/// The [`total()`](SlidingWindowSum::total) is correctly rounded, and so is the numerator $nQ-S^2$ of the variance,
+/// where $S$ and $Q$ are the sums of the values and of their squares, which is computed exactly from the expansions.
+///
+/// Values whose square is not finite (including infinities and NaNs) are kept out of the expansions;
+/// while such a value is in the window, or when $nQ$ or $S^2$ overflows,
+/// the statistics are recomputed from the values of the window.
+///
+/// # Examples
+///
+/// ```
+/// # use compensated_summation::SlidingWindowSum;
+/// let mut window = SlidingWindowSum::new(2);
+/// window += 1e100;
+/// window += 1.0;
+/// assert_eq!(window.push(-1e100), Some(1e100));
+/// assert_eq!(window.total(), 1.0 - 1e100);
+/// window += 1e100;
+/// window += 2.0;
+/// assert_eq!(window.total(), 1e100 + 2.0);
+/// window += 4.0;
+/// assert_eq!(window.total(), 6.0);
+/// assert_eq!(window.mean(), 3.0);
+/// assert_eq!(window.variance(), 1.0);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct SlidingWindowSum<T> {
+    /// Maximum number of values in the window.
+    capacity: usize,
+    /// Values in the window, from the oldest to the newest.
+    values: VecDeque<T>,
+    /// Exact sum of the values in the window whose square is finite.
+    sum: ExactSum<T>,
+    /// Exact sum of the squares of the values in the window whose square is finite.
+    squares: ExactSum<T>,
+    /// Number of values in the window whose square is not finite.
+    nonfinite: usize,
+}
+
+impl<T: Float> SlidingWindowSum<T> {
+    /// Create a new empty window holding at most `capacity` values.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is zero.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity must be positive");
+        Self {
+            capacity,
+            values: VecDeque::with_capacity(capacity),
+            sum: ExactSum::new(),
+            squares: ExactSum::new(),
+            nonfinite: 0,
+        }
+    }
+
+    /// Get the maximum number of values in the window.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Get the number of values in the window.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Whether the window is empty.
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Whether the window holds `capacity` values, so that the next push evicts the oldest one.
+    pub fn is_full(&self) -> bool {
+        self.values.len() == self.capacity
+    }
+
+    /// Iterate over the values in the window, from the oldest to the newest.
+    pub fn values(&self) -> impl Iterator<Item = &T> + '_ {
+        self.values.iter()
+    }
+
+    /// Add a value to the window, evicting and returning the oldest value if the window is full.
+    pub fn push(&mut self, x: T) -> Option<T> {
+        let evicted = if self.is_full() {
+            self.values.pop_front()
+        } else {
+            None
+        };
+        if let Some(y) = evicted {
+            let (p, δp) = two_prod(y, y);
+            if p.is_finite() {
+                self.sum -= y;
+                self.squares -= p;
+                self.squares -= δp;
+            } else {
+                self.nonfinite -= 1;
+            }
+        }
+        let (p, δp) = two_prod(x, x);
+        if p.is_finite() {
+            self.sum += x;
+            self.squares += p;
+            self.squares += δp;
+        } else {
+            self.nonfinite += 1;
+        }
+        self.values.push_back(x);
+        evicted
+    }
+
+    /// Remove all the values from the window.
+    pub fn clear(&mut self) {
+        self.values.clear();
+        self.sum = ExactSum::new();
+        self.squares = ExactSum::new();
+        self.nonfinite = 0;
+    }
+
+    /// Get the sum of the values in the window, correctly rounded to nearest.
+    pub fn total(&self) -> T {
+        if self.nonfinite > 0 {
+            self.values.iter().sum::<ExactSum<T>>().total()
+        } else {
+            self.sum.total()
+        }
+    }
+
+    /// Get the mean of the values in the window.
+    ///
+    /// This is NaN if the window is empty.
+    pub fn mean(&self) -> T {
+        self.total() / T::from(self.len()).unwrap()
+    }
+
+    /// Get the population variance of the values in the window.
+    ///
+    /// This is NaN if the window is empty.
+    pub fn variance(&self) -> T {
+        let n = T::from(self.len()).unwrap();
+        match self.deviations() {
+            Some(d) if self.nonfinite == 0 => d / (n * n),
+            _ => self.values.iter().sum::<Moments<T>>().variance(),
+        }
+    }
+
+    /// Get the sample variance of the values in the window, with Bessel's correction.
+    ///
+    /// This is NaN if fewer than two values are in the window.
+    pub fn sample_variance(&self) -> T {
+        if self.len() < 2 {
+            return T::nan();
+        }
+        let n = T::from(self.len()).unwrap();
+        match self.deviations() {
+            Some(d) if self.nonfinite == 0 => d / (n * (n - T::one())),
+            _ => self.values.iter().sum::<Moments<T>>().sample_variance(),
+        }
+    }
+
+    // Get $nQ-S^2=n\sum_i(x_i-\bar x)^2$, correctly rounded,
+    // or `None` if a product overflows even though the squares of the values are finite.
+    fn deviations(&self) -> Option<T> {
+        let n = T::from(self.len()).unwrap();
+        let mut d = ExactSum::new();
+        for &q in self.squares.components() {
+            let (p, δp) = two_prod(n, q);
+            if !p.is_finite() {
+                return None;
+            }
+            d += p;
+            d += δp;
+        }
+        let s = self.sum.components();
+        for (i, &a) in s.iter().enumerate() {
+            for (j, &b) in s.iter().enumerate().skip(i) {
+                let (p, δp) = two_prod(a, b);
+                // Off-diagonal products appear twice in the square.
+                let k = if i == j {
+                    T::one()
+                } else {
+                    T::one() + T::one()
+                };
+                if !(p * k).is_finite() {
+                    return None;
+                }
+                d -= p * k;
+                d -= δp * k;
+            }
+        }
+        Some(d.total())
+    }
+}
+
+impl<T: Float> AddAssign<T> for SlidingWindowSum<T> {
+    fn add_assign(&mut self, rhs: T) {
+        self.push(rhs);
+    }
+}
+
+impl<T: Float> AddAssign<&T> for SlidingWindowSum<T> {
+    fn add_assign(&mut self, rhs: &T) {
+        self.push(*rhs);
+    }
+}
+
+impl<T: Float, V> Add<V> for SlidingWindowSum<T>
+where
+    Self: AddAssign<V>,
+{
+    type Output = Self;
+    fn add(mut self, rhs: V) -> Self::Output {
+        self += rhs;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn window_no_drift() {
+        use rand::prelude::*;
+        use rand_distr::LogNormal;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(42);
+        let dist = LogNormal::new(0.0, 10.0).unwrap();
+        let values: Vec<f64> = (0..1_000_000)
+            .map(|_| rng.sample(dist) * if rng.gen() { 1.0 } else { -1.0 })
+            .collect();
+        let mut window = SlidingWindowSum::new(100);
+        for (i, &x) in values.iter().enumerate() {
+            if let Some(y) = window.push(x) {
+                assert_eq!(y, values[i - 100]);
+            }
+            if i % 10_000 == 9_999 {
+                let exact = values[i - 99..=i].iter().sum::<ExactSum<f64>>().total();
+                assert_eq!(window.total(), exact);
+            }
+        }
+        let last = &values[values.len() - 100..];
+        let exact = last.iter().sum::<ExactSum<f64>>().total();
+        assert_eq!(window.total(), exact);
+        assert!(window.values().eq(last));
+    }
+
+    #[test]
+    fn window_variance() {
+        use rand::prelude::*;
+        use rand_distr::Normal;
+        use rand_xoshiro::Xoshiro256PlusPlus;
+
+        let mut rng = Xoshiro256PlusPlus::seed_from_u64(7);
+        let dist = Normal::new(1e9, 1e-3).unwrap();
+        let mut window = SlidingWindowSum::new(1000);
+        for _ in 0..10_000 {
+            window += rng.sample(dist);
+        }
+        let values: Vec<f64> = window.values().copied().collect();
+        let moments: Moments<f64> = values.iter().sum();
+        assert_eq!(window.len(), 1000);
+        assert!(window.is_full());
+        assert!((window.mean() - moments.mean()).abs() <= moments.mean() * f64::EPSILON);
+        let var = moments.variance();
+        assert!((window.variance() - var).abs() <= var * 1e-12);
+        let var = moments.sample_variance();
+        assert!((window.sample_variance() - var).abs() <= var * 1e-12);
+    }
+
+    #[test]
+    fn window_nonfinite() {
+        let mut window = SlidingWindowSum::new(3) + 1.0 + f64::INFINITY + 2.0;
+        assert_eq!(window.total(), f64::INFINITY);
+        assert!(window.variance().is_nan());
+        window += 1e200;
+        assert_eq!(window.total(), f64::INFINITY);
+        window += -1e200;
+        assert_eq!(window.total(), 2.0);
+        window += 0.5;
+        assert_eq!(window.total(), 0.5);
+        window += 2.5;
+        window += 4.5;
+        assert_eq!(window.total(), 7.5);
+        assert_eq!(window.variance(), 8.0 / 3.0);
+        assert_eq!(window.sample_variance(), 4.0);
+        window.clear();
+        assert!(window.is_empty());
+        assert!(window.mean().is_nan());
+        assert!(window.sample_variance().is_nan());
+        window += 1.5;
+        assert_eq!(window.total(), 1.5);
+        assert_eq!(window.variance(), 0.0);
+    }
+
+    #[test]
+    fn window_variance_huge() {
+        // The squares are finite, but `nQ` and `S^2` overflow.
+        let x = f64::MAX.sqrt() / 2.0;
+        let mut window = SlidingWindowSum::new(4);
+        for _ in 0..4 {
+            window += 1e154;
+        }
+        assert_eq!(window.variance(), 0.0);
+        assert_eq!(window.sample_variance(), 0.0);
+        for y in [x, x, x, -x] {
+            window += y;
+        }
+        let var = 0.75 * x * x;
+        assert!((window.variance() - var).abs() <= var * 4.0 * f64::EPSILON);
+        let var = x * x;
+        assert!((window.sample_variance() - var).abs() <= var * 4.0 * f64::EPSILON);
+    }
+
+    #[test]
+    #[should_panic]
+    fn window_zero_capacity() {
+        SlidingWindowSum::<f64>::new(0);
+    }
+}